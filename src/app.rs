@@ -4,31 +4,125 @@ use std::{
 };
 
 use crate::{
-    csvfile::CSVFile,
+    csvfile::{self, CSVFile, NonFinitePolicy, NumberFormat, SAMPLE_THRESHOLD_BYTES},
     errors::ErrorStringExt,
-    event::AppEvent,
-    file_entry::{get_file_entries, FileEntry},
-    folder::Folder,
-    plot::PlotDimensions,
+    event::{AppEvent, ResetManipulations, SetActive, SetFolderPlotted},
+    export::ExportStyle,
+    file_entry::{get_file_entries, ClampMode, FileEntry, OffsetMode, RenderMode, StepMode},
+    fit::AverageRange,
+    folder::{file_labels_key, qualify_file_labels, FileLabels, FileSort, Folder},
+    loader::Loader,
+    minimap::Minimap,
+    plot::{
+        AverageCurve, ColorCheck, ColorCycle, CopySeparator, KeyBindings, NudgeStep, PlotOptions,
+        PlotSettings, PlotTabs, SharedCursor, TextAnnotation,
+    },
 };
-use egui::{menu::menu_button, Color32};
+use egui::menu::menu_button;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Default)]
 pub struct App {
     pub folders: Vec<Folder>,
     search_phrase: String,
+    /// recently used search phrases, most recent first
+    #[serde(default)]
+    search_history: Vec<String>,
     //FIXME: plot dimensions are not loaded when restoring session
-    pub plot_dims: PlotDimensions,
+    #[serde(default)]
+    pub plot_tabs: PlotTabs,
+    #[serde(default)]
+    pub plot_options: PlotOptions,
     id_counter: usize,
+    #[serde(default = "default_max_log_entries")]
+    max_log_entries: usize,
+    #[serde(default)]
+    error_log: ErrorLogLayout,
+    #[serde(default)]
+    inspector: InspectorLayout,
+    #[serde(default)]
+    pub export_style: ExportStyle,
+    #[serde(default)]
+    slider_options: SliderOptions,
+    /// if set, drag manipulations only apply to `primary_file`
+    #[serde(default)]
+    pub manipulate_primary_only: bool,
+    #[serde(default)]
+    pub primary_file: Option<usize>,
+    #[serde(default)]
+    pub key_bindings: KeyBindings,
+    #[serde(default)]
+    pub nudge_step: NudgeStep,
+    #[serde(default)]
+    file_sort: FileSort,
+    #[serde(default)]
+    file_labels: FileLabels,
+    #[serde(default)]
+    manipulation_defaults: ManipulationDefaults,
+    #[serde(default)]
+    auto_stack: AutoStack,
+    #[serde(default)]
+    pub copy_separator: CopySeparator,
+    #[serde(default)]
+    pub color_cycle: ColorCycle,
+    #[serde(default)]
+    pub average_range: AverageRange,
+    #[serde(default)]
+    pub averages: Vec<AverageCurve>,
+    #[serde(default)]
+    pub annotations: Vec<TextAnnotation>,
+    /// ids of files whose settings are shown in the side panel
+    #[serde(default)]
+    pinned_files: Vec<usize>,
+    /// kept in the session, to stamp the look of one plot onto another
+    #[serde(default)]
+    pub copied_plot_settings: Option<PlotSettings>,
     #[serde(skip)]
     pub errors: Vec<String>,
+    // entries trimmed from `errors`, oldest first, so that the whole log can
+    // be copied
+    #[serde(skip)]
+    log_history: Vec<String>,
     #[serde(skip)]
     pub acceleration: Option<f64>,
     #[serde(skip)]
     copied_csvoptions: Option<CSVFile>,
     #[serde(skip)]
     queued_events: Vec<Box<dyn AppEvent>>,
+    #[serde(skip)]
+    undo_stack: Vec<Box<dyn AppEvent>>,
+    #[serde(skip)]
+    pub loader: Loader,
+    // file entry id selected with the keyboard in the file tree
+    #[serde(skip)]
+    selected_file: Option<usize>,
+    #[serde(skip)]
+    scroll_to_selected: bool,
+    #[serde(skip)]
+    goto_phrase: String,
+    #[serde(skip)]
+    pub color_check: ColorCheck,
+    #[serde(skip)]
+    pub minimap: Minimap,
+    /// new view of the active plot tab, applied when it is drawn next
+    #[serde(skip)]
+    pub pending_bounds: Option<egui_plot::PlotBounds>,
+    /// the next click into the plot adds a text annotation
+    #[serde(skip)]
+    pub placing_annotation: bool,
+    /// pointer position shared by the plot panels, see `PlotOptions::link_cursor`
+    #[serde(skip)]
+    pub shared_cursor: SharedCursor,
+    // the dialog to confirm resetting all manipulations is open
+    #[serde(skip)]
+    confirm_reset: bool,
+    // the input of the last `qualify_file_labels`, see `file_labels_key`
+    #[serde(skip)]
+    qualified_labels: Option<(FileLabels, Vec<(usize, bool)>)>,
+    // ids of the files plotted in the last frame, `None` until the first
+    // frame, so that files of a restored session are not stacked again
+    #[serde(skip)]
+    plotted_files: Option<Vec<usize>>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -37,92 +131,397 @@ pub struct FloatInput {
 }
 
 impl FloatInput {
+    /// plain numbers, optionally followed by an SI prefix like in `2.5k`
     pub fn parse(&self) -> Option<f64> {
-        self.input.parse().ok()
+        let input = self.input.trim();
+        if let Ok(number) = input.parse() {
+            return Some(number);
+        }
+        let suffix = input.chars().last()?;
+        let factor = match suffix {
+            'G' => 1e9,
+            'M' => 1e6,
+            'k' => 1e3,
+            'm' => 1e-3,
+            'u' | 'µ' => 1e-6,
+            'n' => 1e-9,
+            _ => return None,
+        };
+        let number: f64 = input[..input.len() - suffix.len_utf8()]
+            .trim_end()
+            .parse()
+            .ok()?;
+        Some(number * factor)
+    }
+}
+
+/// scale and offsets of files in newly opened folders
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ManipulationDefaults {
+    pub scale: FloatInput,
+    pub offset: FloatInput,
+    pub xoffset: FloatInput,
+}
+
+impl Default for ManipulationDefaults {
+    fn default() -> Self {
+        Self {
+            scale: FloatInput {
+                input: "1.0".to_string(),
+            },
+            offset: FloatInput {
+                input: "0.0".to_string(),
+            },
+            xoffset: FloatInput {
+                input: "0.0".to_string(),
+            },
+        }
+    }
+}
+
+/// size and placement of the error log
+#[derive(Serialize, Deserialize)]
+pub struct ErrorLogLayout {
+    pub height: f32,
+    /// only the header row of the log is shown
+    pub collapsed: bool,
+    /// the log is shown in its own window instead of below the plot
+    pub detached: bool,
+}
+
+impl Default for ErrorLogLayout {
+    fn default() -> Self {
+        Self {
+            height: 100.0,
+            collapsed: false,
+            detached: false,
+        }
+    }
+}
+
+// the header row and at least one line of the error log stay visible
+const MIN_ERROR_LOG_HEIGHT: f32 = 50.0;
+
+/// the side panel listing all plotted files
+#[derive(Serialize, Deserialize)]
+pub struct InspectorLayout {
+    pub width: f32,
+    pub visible: bool,
+}
+
+impl Default for InspectorLayout {
+    fn default() -> Self {
+        Self {
+            width: 300.0,
+            visible: false,
+        }
+    }
+}
+
+// width of the scale and offset fields in the inspector
+const INSPECTOR_FIELD_WIDTH: f32 = 80.0;
+
+/// offset newly plotted files by one step more than the previous one, for
+/// waterfall plots
+#[derive(Serialize, Deserialize)]
+pub struct AutoStack {
+    pub enabled: bool,
+    /// y-offset between consecutive files, in the unit of their offset mode
+    pub step: f64,
+    // ids of the files that got an offset of the stack
+    #[serde(skip)]
+    stacked: Vec<usize>,
+}
+
+impl Default for AutoStack {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            step: 1.0,
+            stacked: Vec::new(),
+        }
+    }
+}
+
+/// step and range of the sliders next to `FloatInput` fields
+#[derive(Serialize, Deserialize)]
+pub struct SliderOptions {
+    pub step: f64,
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Default for SliderOptions {
+    fn default() -> Self {
+        Self {
+            step: 0.01,
+            min: -1e6,
+            max: 1e6,
+        }
     }
 }
 
 impl eframe::App for App {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // the UI is only repainted on input, background loads wake it up
+        self.loader.set_repaint_context(ctx);
         // handle all events
         let mut events = std::mem::take(&mut self.queued_events);
         for mut event in events.drain(..) {
             event.run(self);
+            if event.undoable() {
+                self.undo_stack.push(event);
+            }
+        }
+        if !ctx.wants_keyboard_input()
+            && ctx.input_mut(|i| i.consume_key(egui::Modifiers::COMMAND, egui::Key::Z))
+        {
+            self.undo();
+        }
+        // Tab moves the keyboard focus while a widget has it
+        if !ctx.wants_keyboard_input() && ctx.memory(|mem| mem.focused().is_none()) {
+            // shift has to be checked first, consume_key ignores it otherwise
+            let backwards =
+                ctx.input_mut(|i| i.consume_key(egui::Modifiers::SHIFT, egui::Key::Tab));
+            if backwards || ctx.input_mut(|i| i.consume_key(egui::Modifiers::NONE, egui::Key::Tab))
+            {
+                self.cycle_active_file(backwards);
+            }
+        }
+        // before the file tree takes the arrow keys for its navigation
+        self.nudge_offsets(ctx);
+
+        // hand over files that finished loading in the background
+        for result in self.loader.finished() {
+            if let Some(file_entry) = self
+                .folders
+                .iter_mut()
+                .flat_map(|folder| &mut folder.files)
+                .find(|file_entry| file_entry.id == result.file_id)
+            {
+                if file_entry.finish_loading(result.generation, result.csvfile, result.parsed_empty)
+                {
+                    self.errors.extend(result.errors);
+                }
+            }
+        }
+
+        let labels_key = Some((self.file_labels, file_labels_key(&self.folders)));
+        if self.qualified_labels != labels_key {
+            qualify_file_labels(&mut self.folders, self.file_labels);
+            self.qualified_labels = labels_key;
         }
 
         egui::panel::TopBottomPanel::top("Menu").show(ctx, |ui| self.menu(ui));
-        egui::panel::TopBottomPanel::bottom("Error Log")
-            .exact_height(100.0)
-            .show(ctx, |ui| {
-                // only retain the last 10 errors
-                if self.errors.len() > 10 {
-                    let n = self.errors.len().saturating_sub(10);
-                    self.errors = self.errors[n..].to_vec();
-                };
-                ui.label("Error log:");
-                ui.label(self.errors.join("\n"));
-            });
+        self.trim_log();
+        if self.error_log.detached {
+            let mut open = true;
+            egui::Window::new("Error Log")
+                .open(&mut open)
+                .default_height(self.error_log.height)
+                .show(ctx, |ui| {
+                    self.error_log_header_ui(ui);
+                    self.error_log_ui(ui);
+                });
+            // closing the window puts the log back into the panel
+            if !open {
+                self.error_log.detached = false;
+            }
+        } else if self.error_log.collapsed {
+            egui::panel::TopBottomPanel::bottom("Error Log Collapsed")
+                .show(ctx, |ui| self.error_log_header_ui(ui));
+        } else {
+            let panel = egui::panel::TopBottomPanel::bottom("Error Log")
+                .resizable(true)
+                .default_height(self.error_log.height)
+                .height_range(MIN_ERROR_LOG_HEIGHT..=f32::INFINITY)
+                .show(ctx, |ui| {
+                    self.error_log_header_ui(ui);
+                    self.error_log_ui(ui);
+                });
+            self.error_log.height = panel.response.rect.height();
+        }
 
+        self.confirm_reset_ui(ctx);
+        self.inspector_ui(ctx);
+        self.pinned_settings_ui(ctx);
+        self.auto_stack_plotted();
         self.plot_panel_ui(ctx);
     }
 }
 
-fn file_settings_menu(
-    ui: &mut egui::Ui,
-    file_entry: &mut FileEntry,
-    folder_path: &Path,
-    csv_options: &mut Option<CSVFile>,
-    error_log: &mut Vec<String>,
-) {
-    ui.heading("CSV Settings");
+/// regular expression that replaces the splitting into fields, with an
+/// indicator whether it matches the first lines of the file
+fn field_pattern_ui(ui: &mut egui::Ui, options: &mut CSVFile, preview: &str) {
+    ui.horizontal(|ui| {
+        ui.label("Field pattern")
+            .on_hover_text("Regular expression with the named groups (?<x>...) and (?<y>...), used instead of the delimiter if not empty");
+        ui.text_edit_singleline(&mut options.field_pattern);
+    });
+    if options.field_pattern.is_empty() {
+        return;
+    }
+    match csvfile::compile_field_pattern(&options.field_pattern, options.x_is_index) {
+        Ok(regex) => {
+            let num_lines = preview.lines().count();
+            let num_matched = preview.lines().filter(|line| regex.is_match(line)).count();
+            let color = if num_matched > 0 {
+                ui.visuals().text_color()
+            } else {
+                ui.visuals().warn_fg_color
+            };
+            ui.colored_label(
+                color,
+                format!("matches {num_matched} of the first {num_lines} lines"),
+            );
+        }
+        Err(err) => {
+            ui.colored_label(ui.visuals().error_fg_color, err);
+        }
+    }
+}
 
+/// widgets for the parse options of a CSV file
+fn csv_options_ui(ui: &mut egui::Ui, options: &mut CSVFile) {
     ui.label("x-Column:");
-    integer_edit_field(ui, &mut file_entry.data_file.xcol);
+    integer_edit_field(ui, &mut options.xcol);
     ui.label("y-Column:");
-    integer_edit_field(ui, &mut file_entry.data_file.ycol);
+    integer_edit_field(ui, &mut options.ycol);
 
-    ui.label("Skip header lines:");
-    integer_edit_field(ui, &mut file_entry.data_file.skip_header);
-    ui.label("Skip footer files:");
-    integer_edit_field(ui, &mut file_entry.data_file.skip_footer);
+    ui.checkbox(&mut options.auto_columns, "Detect columns on next load")
+        .on_hover_text(
+            "Use the first increasing numeric column as x and the next numeric column as y.\n\
+             The configured columns are used if no such column is found.",
+        );
+
+    ui.checkbox(&mut options.load_full, "Load full file")
+        .on_hover_text(format!(
+            "Files above {} MB are sampled for a responsive plot,\n\
+             load them completely, e.g. before exporting.",
+            SAMPLE_THRESHOLD_BYTES / 1_000_000
+        ));
+
+    ui.checkbox(&mut options.keep_rows, "Remember rows")
+        .on_hover_text(
+            "Keep the row of every point, to show it on hover in the plot.\n\
+             Takes effect on the next load and needs extra memory.",
+        );
+
+    ui.checkbox(&mut options.x_is_index, "Use row index as x")
+        .on_hover_text("Ignore the x-column and use the 0-based row index as x.");
+
+    ui.checkbox(&mut options.parametric, "Parametric curve")
+        .on_hover_text("Plot two value columns against each other, in the order of the rows.");
+    if options.parametric {
+        ui.label("Parametric x-Column:");
+        integer_edit_field(ui, &mut options.param_xcol);
+        ui.label("Parametric y-Column:");
+        integer_edit_field(ui, &mut options.param_ycol);
+    }
+
+    ui.horizontal(|ui| {
+        ui.label("Non-finite y-values:");
+        egui::ComboBox::from_id_source("non_finite")
+            .selected_text(options.non_finite.label())
+            .show_ui(ui, |ui| {
+                for policy in NonFinitePolicy::ALL {
+                    ui.selectable_value(&mut options.non_finite, policy, policy.label());
+                }
+            });
+    });
+
+    ui.label("Keep every n-th row:");
+    integer_edit_field(ui, &mut options.stride);
+
+    ui.label("Skip header lines:").on_hover_text(
+        "Lines removed from the start of the file, e.g. a preamble before the column names.",
+    );
+    integer_edit_field(ui, &mut options.skip_header);
+    ui.label("Skip footer lines:").on_hover_text(
+        "Lines removed from the end of the file, blank lines at the end are not counted.",
+    );
+    integer_edit_field(ui, &mut options.skip_footer);
 
     let lab = ui.label("Delimiter");
-    let mut delimiter =
-        String::from_utf8(vec![file_entry.data_file.delimiter]).unwrap_or("#".into());
+    let mut delimiter = String::from_utf8(vec![options.delimiter]).unwrap_or("#".into());
     ui.text_edit_singleline(&mut delimiter).labelled_by(lab.id);
     if let Some(ch) = delimiter.as_bytes().first() {
-        file_entry.data_file.delimiter = *ch;
+        options.delimiter = *ch;
     }
+    ui.horizontal(|ui| {
+        ui.label("Numbers like");
+        egui::ComboBox::from_id_source("number_format")
+            .selected_text(options.number_format.label())
+            .show_ui(ui, |ui| {
+                for format in NumberFormat::ALL {
+                    ui.selectable_value(&mut options.number_format, format, format.label());
+                }
+            });
+    })
+    .response
+    .on_hover_text("Decimal and thousands separators, a decimal comma needs another delimiter");
     let lab = ui.label("Comment character");
-    let mut char = String::from_utf8(vec![file_entry.data_file.comment_char]).unwrap_or("#".into());
+    let mut char = String::from_utf8(vec![options.comment_char]).unwrap_or("#".into());
     ui.text_edit_singleline(&mut char).labelled_by(lab.id);
     if let Some(ch) = char.as_bytes().first() {
-        file_entry.data_file.comment_char = *ch;
+        options.comment_char = *ch;
     }
+    ui.checkbox(&mut options.strip_inline_comments, "Strip inline comments")
+        .on_hover_text(
+            "Ignore everything after the comment character, also in the middle of a line.\n\
+             Breaks quoted fields that contain the comment character.",
+        );
+    let lab = ui.label("Comment prefix");
+    ui.text_edit_singleline(&mut options.comment_prefix)
+        .labelled_by(lab.id)
+        .on_hover_text("Also skip lines starting with this, e.g. `//` or `%`.");
+    ui.checkbox(&mut options.skip_blank_lines, "Skip blank lines")
+        .on_hover_text("Drop lines holding only whitespace, e.g. between data blocks.");
+}
+
+fn file_settings_menu(
+    ui: &mut egui::Ui,
+    file_entry: &mut FileEntry,
+    folder_path: &Path,
+    csv_options: &mut Option<CSVFile>,
+    slider_options: &mut SliderOptions,
+    plot_tabs: &PlotTabs,
+    error_log: &mut Vec<String>,
+) -> Option<bool> {
+    let mut set_active = None;
+    if file_entry.is_plotted() {
+        let (text, active) = if file_entry.is_active() {
+            ("Deactivate", false)
+        } else {
+            ("Make Active", true)
+        };
+        if ui
+            .button(text)
+            .on_hover_text("Drag manipulations apply to active files")
+            .clicked()
+        {
+            set_active = Some(active);
+        }
+    }
+
+    ui.heading("CSV Settings");
+    if let Some(parse_stats) = file_entry.parse_stats_text() {
+        ui.weak(parse_stats)
+            .on_hover_text("Line counts of the last load, reload after changing the settings");
+    }
+
+    csv_options_ui(ui, &mut file_entry.data_file);
+    field_pattern_ui(ui, &mut file_entry.data_file, &file_entry.preview);
 
     ui.horizontal(|ui| {
         if ui.button("Copy Options").clicked() {
-            let csv_tempate = CSVFile {
-                delimiter: file_entry.data_file.delimiter,
-                comment_char: file_entry.data_file.comment_char,
-                xcol: file_entry.data_file.xcol,
-                ycol: file_entry.data_file.ycol,
-                skip_header: file_entry.data_file.skip_header,
-                skip_footer: file_entry.data_file.skip_footer,
-                ..Default::default()
-            };
-            *csv_options = Some(csv_tempate);
+            *csv_options = Some(file_entry.data_file.options());
         }
 
         match csv_options {
             Some(opts) => {
                 if ui.button("Paste Options").clicked() {
-                    file_entry.data_file.delimiter = opts.delimiter;
-                    file_entry.data_file.comment_char = opts.comment_char;
-                    file_entry.data_file.xcol = opts.xcol;
-                    file_entry.data_file.ycol = opts.ycol;
-                    file_entry.data_file.skip_header = opts.skip_header;
-                    file_entry.data_file.skip_footer = opts.skip_footer;
+                    file_entry.data_file.apply_options(opts);
                 }
             }
             None => {
@@ -132,16 +531,310 @@ fn file_settings_menu(
     });
 
     ui.heading("Manipulation");
+    ui.checkbox(&mut file_entry.cumulative_sum, "Cumulative sum")
+        .on_hover_text("Plot the running sum of y, scale and offset apply to the sum");
+    if ui
+        .checkbox(&mut file_entry.zero_at_start, "Zero at start")
+        .on_hover_text("Subtract the first y-value from all points")
+        .changed()
+        && file_entry.zero_at_start
+        && !file_entry.has_start_value()
+    {
+        error_log.push(format!(
+            "WARNING: {} has no finite data point to start at zero.",
+            file_entry.filename
+        ));
+    }
+    if ui
+        .checkbox(&mut file_entry.normalize_x, "Normalize x")
+        .on_hover_text("Map the x-range of the data onto 0..1, before the x-offset")
+        .changed()
+        && file_entry.normalize_x
+        && !file_entry.has_x_range()
+    {
+        error_log.push(format!(
+            "WARNING: the x-values of {} span no range, x is not normalized.",
+            file_entry.filename
+        ));
+    }
+    ui.horizontal(|ui| {
+        let toggled = ui
+            .checkbox(&mut file_entry.decibel, "Decibel, reference")
+            .on_hover_text("Plot 20·log10(|y| / reference), before scale and offset")
+            .changed();
+        ui.add_enabled(
+            file_entry.decibel,
+            egui::DragValue::new(&mut file_entry.decibel_ref)
+                .speed(0.01)
+                .range(f64::MIN_POSITIVE..=f64::MAX),
+        );
+        if toggled && file_entry.decibel {
+            let num_zeros = file_entry.num_zero_values();
+            if num_zeros > 0 {
+                error_log.push(format!(
+                    "WARNING: {} zero y-values of {} have no decibel level and are left out.",
+                    num_zeros, file_entry.filename
+                ));
+            }
+        }
+    });
+    ui.checkbox(&mut file_entry.show_original, "Show original")
+        .on_hover_text("Draw the untransformed data faintly behind the curve");
     ui.label("Scale");
-    ui.text_edit_singleline(&mut file_entry.scale.input);
-    ui.label("y-Offset");
-    ui.text_edit_singleline(&mut file_entry.offset.input);
+    ui.horizontal(|ui| {
+        float_text_field(ui, &mut file_entry.scale);
+        float_drag_field(ui, &mut file_entry.scale, slider_options);
+    });
+    ui.horizontal(|ui| {
+        // only the resulting scale is stored, the divisor is kept in the
+        // egui memory while it is typed
+        let id = ui.id().with(("scale_divisor", file_entry.id));
+        let mut divisor = FloatInput {
+            input: ui
+                .data_mut(|d| d.get_temp::<String>(id))
+                .unwrap_or_default(),
+        };
+        ui.label("Divide by");
+        let response = ui.add(egui::TextEdit::singleline(&mut divisor.input).desired_width(60.0));
+        let apply = ui.button("Apply").clicked()
+            || (response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)));
+        if apply {
+            match divisor.parse() {
+                Some(value) if value != 0.0 => file_entry.scale.input = format!("{}", 1.0 / value),
+                Some(_) => error_log.push("INFO: can not scale by division by zero.".to_string()),
+                None => error_log.push(format!(
+                    "WARNING: divisor {:?} is not a number.",
+                    divisor.input
+                )),
+            }
+        }
+        ui.data_mut(|d| d.insert_temp(id, divisor.input));
+    });
+    ui.horizontal(|ui| {
+        ui.label("y-Offset");
+        egui::ComboBox::from_id_source("offset_mode")
+            .selected_text(file_entry.offset_mode.label())
+            .show_ui(ui, |ui| {
+                for mode in OffsetMode::ALL {
+                    ui.selectable_value(&mut file_entry.offset_mode, mode, mode.label());
+                }
+            })
+            .response
+            .on_hover_text("Multiples of the file's own y-range stack curves of any magnitude");
+    });
+    ui.horizontal(|ui| {
+        float_text_field(ui, &mut file_entry.offset);
+        float_drag_field(ui, &mut file_entry.offset, slider_options);
+    });
     ui.label("x-Offset");
-    ui.text_edit_singleline(&mut file_entry.xoffset.input);
+    ui.horizontal(|ui| {
+        float_text_field(ui, &mut file_entry.xoffset);
+        float_drag_field(ui, &mut file_entry.xoffset, slider_options);
+    });
+    ui.collapsing("Slider options", |ui| {
+        egui::Grid::new("slider_options")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("Step");
+                ui.add(egui::DragValue::new(&mut slider_options.step).speed(0.001));
+                ui.end_row();
+                ui.label("Minimum");
+                ui.add(egui::DragValue::new(&mut slider_options.min));
+                ui.end_row();
+                ui.label("Maximum");
+                ui.add(egui::DragValue::new(&mut slider_options.max));
+                ui.end_row();
+            });
+    });
+
+    ui.heading("Display");
+    ui.horizontal(|ui| {
+        ui.label("Group");
+        let mut group = file_entry.group.clone().unwrap_or_default();
+        if ui
+            .text_edit_singleline(&mut group)
+            .on_hover_text("Files of a group are listed together, leave empty for none")
+            .changed()
+        {
+            file_entry.group = (!group.is_empty()).then_some(group);
+        }
+    });
+    if plot_tabs.all().len() > 1 {
+        ui.horizontal(|ui| {
+            ui.label("Plot tab");
+            let selected = plot_tabs
+                .all()
+                .iter()
+                .find(|tab| file_entry.tab == Some(tab.id))
+                .map(|tab| tab.name.as_str())
+                .unwrap_or_default();
+            egui::ComboBox::from_id_source("plot_tab")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    for tab in plot_tabs.all() {
+                        ui.selectable_value(&mut file_entry.tab, Some(tab.id), &tab.name);
+                    }
+                });
+        });
+    }
+    ui.horizontal(|ui| {
+        ui.label("Render as");
+        egui::ComboBox::from_id_source("render_mode")
+            .selected_text(file_entry.render_mode.label())
+            .show_ui(ui, |ui| {
+                for mode in RenderMode::ALL {
+                    ui.selectable_value(&mut file_entry.render_mode, mode, mode.label());
+                }
+            });
+        if file_entry.render_mode == RenderMode::LinePoints {
+            ui.add(
+                egui::DragValue::new(&mut file_entry.marker_size)
+                    .speed(0.1)
+                    .range(0.5..=20.0)
+                    .prefix("marker size "),
+            );
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut file_entry.fill, "Fill area down to");
+        ui.add_enabled(
+            file_entry.fill,
+            egui::DragValue::new(&mut file_entry.fill_baseline).speed(0.1),
+        );
+        ui.add_enabled(
+            file_entry.fill,
+            egui::Slider::new(&mut file_entry.fill_alpha, 0.05..=1.0).text("opacity"),
+        );
+    });
+    ui.horizontal(|ui| {
+        ui.label("Interpolation");
+        egui::ComboBox::from_id_source("step_mode")
+            .selected_text(file_entry.step_mode.label())
+            .show_ui(ui, |ui| {
+                for mode in StepMode::ALL {
+                    ui.selectable_value(&mut file_entry.step_mode, mode, mode.label());
+                }
+            });
+    });
+    ui.horizontal(|ui| {
+        let mut break_lines = file_entry.gap_threshold.is_some();
+        ui.checkbox(&mut break_lines, "Break line at x-gaps above");
+        match (break_lines, file_entry.gap_threshold) {
+            (true, None) => file_entry.gap_threshold = Some(1.0),
+            (false, Some(_)) => file_entry.gap_threshold = None,
+            _ => (),
+        }
+        if let Some(threshold) = &mut file_entry.gap_threshold {
+            ui.add(
+                egui::DragValue::new(threshold)
+                    .speed(0.1)
+                    .range(0.0..=f64::INFINITY),
+            );
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("Trim x")
+            .on_hover_text("Drop the points outside of these bounds before all transforms");
+        let (data_min, data_max) = file_entry.data_x_bounds().unwrap_or((0.0, 1.0));
+        for (label, bound, start) in [
+            ("from", &mut file_entry.x_min, data_min),
+            ("to", &mut file_entry.x_max, data_max),
+        ] {
+            let mut trim = bound.is_some();
+            ui.checkbox(&mut trim, label);
+            match (trim, *bound) {
+                // start at the end of the data, so nothing is dropped yet
+                (true, None) => *bound = Some(start),
+                (false, Some(_)) => *bound = None,
+                _ => (),
+            }
+            if let Some(value) = bound {
+                ui.add(egui::DragValue::new(value).speed(0.1));
+            }
+        }
+    });
+    ui.horizontal(|ui| {
+        let mut clamp = file_entry.y_clamp.is_some();
+        ui.checkbox(&mut clamp, "Limit y to").on_hover_text(
+            "Clip or drop plotted y-values outside of a range, after all transforms",
+        );
+        match (clamp, file_entry.y_clamp) {
+            // start with the current range of the data
+            (true, None) => {
+                file_entry.y_clamp = Some(
+                    file_entry
+                        .stats()
+                        .map_or((0.0, 1.0), |stats| (stats.ymin, stats.ymax)),
+                )
+            }
+            (false, Some(_)) => file_entry.y_clamp = None,
+            _ => (),
+        }
+        if let Some((lo, hi)) = &mut file_entry.y_clamp {
+            ui.add(egui::DragValue::new(lo).speed(0.1).range(f64::MIN..=*hi));
+            ui.label("…");
+            ui.add(egui::DragValue::new(hi).speed(0.1).range(*lo..=f64::MAX));
+            egui::ComboBox::from_id_source("clamp_mode")
+                .selected_text(file_entry.clamp_mode.label())
+                .show_ui(ui, |ui| {
+                    for mode in ClampMode::ALL {
+                        ui.selectable_value(&mut file_entry.clamp_mode, mode, mode.label());
+                    }
+                });
+        }
+    });
+
+    ui.heading("Analysis");
+    if let Some(stats) = file_entry.stats() {
+        egui::Grid::new("stats").num_columns(2).show(ui, |ui| {
+            ui.label("y min:");
+            ui.label(format!("{:.6e}", stats.ymin));
+            ui.end_row();
+            ui.label("y max:");
+            ui.label(format!("{:.6e}", stats.ymax));
+            ui.end_row();
+            ui.label("y mean:");
+            ui.label(format!("{:.6e}", stats.ymean));
+            ui.end_row();
+            ui.label("x range:");
+            ui.label(format!("{:.6e} … {:.6e}", stats.xmin, stats.xmax));
+            ui.end_row();
+        });
+    }
+    ui.checkbox(&mut file_entry.fit_linear, "Fit linear");
+    if file_entry.fit_linear {
+        match file_entry.linear_fit() {
+            Some(fit) => {
+                ui.label(format!("slope: {:.6e}", fit.slope));
+                ui.label(format!("intercept: {:.6e}", fit.intercept));
+                ui.label(format!("R²: {:.6}", fit.r_squared));
+            }
+            None => {
+                ui.label("Not enough distinct x-values for a linear fit.");
+            }
+        }
+    }
 
     if ui.button("Reload CSV").clicked() {
-        return file_entry.reload_csv(folder_path, error_log);
+        file_entry.reload_csv(folder_path, error_log);
+        return set_active;
     }
+    ui.horizontal(|ui| {
+        if ui
+            .button("Open File")
+            .on_hover_text("Open the file with the default program, e.g. a text editor")
+            .clicked()
+        {
+            if let Err(msg) = file_entry.open_externally(folder_path) {
+                error_log.push(msg);
+            }
+        }
+        if ui.button("Show in Folder").clicked() {
+            if let Err(msg) = file_entry.reveal_in_file_manager(folder_path) {
+                error_log.push(msg);
+            }
+        }
+    });
 
     ui.menu_button("Color", |ui| {
         egui::color_picker::color_picker_color32(
@@ -150,12 +843,14 @@ fn file_settings_menu(
             egui::color_picker::Alpha::BlendOrAdditive,
         );
     });
+    set_active
 }
 
 impl App {
     pub fn with_search_phrase(phrase: &str) -> Self {
         App {
             search_phrase: String::from(phrase),
+            max_log_entries: default_max_log_entries(),
             ..Default::default()
         }
     }
@@ -177,11 +872,282 @@ impl App {
                 if folder_label.clicked() {
                     folder.expanded = !folder.expanded;
                 }
+                ui.menu_button("Defaults", |ui| {
+                    ui.label("CSV settings for files of this folder that were not loaded yet:");
+                    csv_options_ui(ui, &mut folder.default_options);
+                });
+                if ui.small_button("Plot all").clicked() {
+                    self.queued_events.push(Box::new(SetFolderPlotted::new(
+                        folder.path.clone(),
+                        &self.search_phrase,
+                        true,
+                    )));
+                }
+                if ui.small_button("Hide all").clicked() {
+                    self.queued_events.push(Box::new(SetFolderPlotted::new(
+                        folder.path.clone(),
+                        &self.search_phrase,
+                        false,
+                    )));
+                }
             });
-            folder.list_files_ui(ui, &self.search_phrase, &mut self.errors);
+            if let Some((id, active)) = folder.list_files_ui(
+                ui,
+                &self.search_phrase,
+                &mut self.loader,
+                self.selected_file,
+                self.scroll_to_selected,
+                self.file_sort,
+            ) {
+                self.queued_events
+                    .push(Box::new(SetActive::new(id, active)));
+            }
+        }
+        self.scroll_to_selected = false;
+    }
+
+    /// search files in all folders and jump to them in the tree
+    fn goto_file_ui(&mut self, ui: &mut egui::Ui) {
+        const MAX_RESULTS: usize = 10;
+        let lab = ui.label("Go to file:");
+        ui.text_edit_singleline(&mut self.goto_phrase)
+            .labelled_by(lab.id);
+        if self.goto_phrase.is_empty() {
+            return;
+        }
+        let mut goto = None;
+        let matches = self.folders.iter().flat_map(|folder| {
+            folder
+                .files
+                .iter()
+                .filter(|file_entry| file_entry.matches_search_phrase(&self.goto_phrase))
+                .map(move |file_entry| (folder, file_entry))
+        });
+        for (folder, file_entry) in matches.take(MAX_RESULTS) {
+            let text = format!("{}/{}", folder.path.to_string_lossy(), file_entry.filename);
+            if ui.selectable_label(false, text).clicked() {
+                goto = Some(file_entry.id);
+            }
+        }
+        if let Some(id) = goto {
+            for folder in self.folders.iter_mut() {
+                if folder.files.iter().any(|file_entry| file_entry.id == id) {
+                    folder.expanded = true;
+                }
+            }
+            self.selected_file = Some(id);
+            self.scroll_to_selected = true;
+            self.goto_phrase.clear();
+        }
+        ui.separator();
+    }
+
+    /// move the selection in the file tree with the arrow keys and toggle
+    /// the selected file with enter
+    fn file_tree_keyboard_navigation(&mut self, ui: &egui::Ui) {
+        use egui::{Key, Modifiers};
+        // do not steal keys from text inputs
+        if ui.ctx().wants_keyboard_input() {
+            return;
+        }
+        let (up, down, enter) = ui.input_mut(|i| {
+            (
+                i.consume_key(Modifiers::NONE, Key::ArrowUp),
+                i.consume_key(Modifiers::NONE, Key::ArrowDown),
+                i.consume_key(Modifiers::NONE, Key::Enter),
+            )
+        });
+        if !(up || down || enter) {
+            return;
+        }
+        let mut listed: Vec<usize> = Vec::new();
+        for folder in self.folders.iter_mut() {
+            for idx in folder.sorted_indices(self.file_sort) {
+                let file_entry = &folder.files[idx];
+                if file_entry.should_be_listed(&self.search_phrase, folder.expanded)
+                    && !folder.is_collapsed(file_entry)
+                {
+                    listed.push(file_entry.id);
+                }
+            }
+        }
+        let position = self
+            .selected_file
+            .and_then(|id| listed.iter().position(|listed_id| *listed_id == id));
+        let new_position = match position {
+            None => 0,
+            Some(pos) if up => pos.saturating_sub(1),
+            Some(pos) if down => pos + 1,
+            Some(pos) => pos,
+        };
+        let Some(id) = listed.get(new_position.min(listed.len().saturating_sub(1))) else {
+            self.selected_file = None;
+            return;
+        };
+        self.selected_file = Some(*id);
+        self.scroll_to_selected = true;
+        // only toggle a file that was already selected
+        if !enter || position.is_none() {
+            return;
+        }
+        for folder in self.folders.iter_mut() {
+            if let Some(file_entry) = folder.files.iter_mut().find(|f| f.id == *id) {
+                file_entry.clicked(&folder.path, &folder.default_options, &mut self.loader);
+            }
         }
     }
 
+    /// buttons to hide, detach and copy the error log and its size limit
+    fn error_log_header_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if !self.error_log.detached {
+                let (text, hint) = if self.error_log.collapsed {
+                    ("Show", "Show the error log")
+                } else {
+                    ("Hide", "Hide the error log")
+                };
+                if ui.small_button(text).on_hover_text(hint).clicked() {
+                    self.error_log.collapsed = !self.error_log.collapsed;
+                }
+            }
+            if self.error_log.collapsed && !self.error_log.detached {
+                ui.label(format!("Error log ({} entries)", self.errors.len()));
+            } else {
+                ui.label("Error log:");
+            }
+            ui.add(
+                egui::DragValue::new(&mut self.max_log_entries)
+                    .range(1..=1000)
+                    .prefix("keep last "),
+            );
+            let (text, hint) = if self.error_log.detached {
+                ("Dock", "Show the error log below the plot")
+            } else {
+                ("Detach", "Show the error log in a window")
+            };
+            if ui.small_button(text).on_hover_text(hint).clicked() {
+                self.error_log.detached = !self.error_log.detached;
+            }
+            if ui
+                .add_enabled(!self.errors.is_empty(), egui::Button::new("Copy").small())
+                .on_hover_text(
+                    "Copy all entries of the log to the clipboard, \
+                     including those that are no longer shown",
+                )
+                .clicked()
+            {
+                ui.output_mut(|o| o.copied_text = self.log_text());
+            }
+            self.load_progress_ui(ui);
+        });
+    }
+
+    /// only show the last `max_log_entries` errors, older ones are kept in
+    /// the history
+    fn trim_log(&mut self) {
+        let n = self.errors.len().saturating_sub(self.max_log_entries);
+        self.log_history.extend(self.errors.drain(..n));
+        let n = self.log_history.len().saturating_sub(MAX_LOG_HISTORY);
+        self.log_history.drain(..n);
+    }
+
+    /// the history and the shown entries of the error log, one per line
+    fn log_text(&self) -> String {
+        let entries: Vec<&str> = self
+            .log_history
+            .iter()
+            .chain(&self.errors)
+            .map(String::as_str)
+            .collect();
+        entries.join("\n")
+    }
+
+    /// the entries of the error log, scrolled to the newest
+    fn error_log_ui(&mut self, ui: &mut egui::Ui) {
+        egui::ScrollArea::vertical()
+            .auto_shrink(false)
+            .stick_to_bottom(true)
+            .show(ui, |ui| ui.label(self.errors.join("\n")));
+    }
+
+    /// progress of the background loads, with a button to cancel them
+    fn load_progress_ui(&mut self, ui: &mut egui::Ui) {
+        let Some((done, total)) = self.loader.progress() else {
+            return;
+        };
+        ui.separator();
+        ui.add(
+            egui::ProgressBar::new(done as f32 / total as f32)
+                .desired_width(200.0)
+                .text(format!("{} of {} files loaded", done, total)),
+        );
+        if ui.button("Cancel").clicked() {
+            // files that finished loading stay plotted
+            self.loader.cancel();
+            for file_entry in self.folders.iter_mut().flat_map(|folder| &mut folder.files) {
+                file_entry.cancel_loading();
+            }
+        }
+    }
+
+    /// re-read the data of all plotted files from disk
+    fn reload_plotted(&mut self) {
+        let (mut num_reloaded, mut num_failed) = (0, 0);
+        for folder in self.folders.iter_mut() {
+            for file_entry in folder.files.iter_mut() {
+                if !file_entry.is_plotted() {
+                    continue;
+                }
+                if file_entry.reload_csv(&folder.path, &mut self.errors) {
+                    num_reloaded += 1;
+                } else {
+                    num_failed += 1;
+                }
+            }
+        }
+        self.errors.push(format!(
+            "INFO: reloaded {} plotted files, {} failed.",
+            num_reloaded, num_failed
+        ));
+    }
+
+    fn undo(&mut self) {
+        if let Some(mut event) = self.undo_stack.pop() {
+            let errors = event.undo(self);
+            self.errors.extend(errors);
+        }
+    }
+
+    /// make the next (or previous) file of the plot tab the only active one,
+    /// this is not undoable, like moving a selection
+    fn cycle_active_file(&mut self, backwards: bool) {
+        let tab = self.plot_tabs.active().id;
+        let files: Vec<(usize, bool)> = self
+            .folders
+            .iter()
+            .flat_map(|folder| &folder.files)
+            .filter(|file_entry| file_entry.is_drawn_in(tab))
+            .map(|file_entry| (file_entry.id, file_entry.is_active()))
+            .collect();
+        if files.is_empty() {
+            return;
+        }
+        // with several active files, the primary one is the reference
+        let current = files
+            .iter()
+            .position(|(id, active)| *active && Some(*id) == self.primary_file)
+            .or_else(|| files.iter().position(|(_, active)| *active));
+        let n = files.len();
+        let next = match (current, backwards) {
+            (Some(i), false) => (i + 1) % n,
+            (Some(i), true) => (i + n - 1) % n,
+            (None, false) => 0,
+            (None, true) => n - 1,
+        };
+        self.queued_events
+            .push(Box::new(SetActive::cycled(files[next].0)));
+    }
+
     fn delete_folders(&mut self) {
         self.folders = self
             .folders
@@ -193,7 +1159,7 @@ impl App {
             .collect();
     }
 
-    fn load_state(&mut self, path: Option<PathBuf>) -> Result<(), String> {
+    pub fn load_state(&mut self, path: Option<PathBuf>) -> Result<(), String> {
         // if no path is given, load from home directory
         let path = match path {
             Some(path) => path,
@@ -212,6 +1178,17 @@ impl App {
             path.to_string_lossy(),
         ))?;
         *self = state;
+        if let Some(color) = self.export_style.mesh.take() {
+            self.plot_options.grid.color = color;
+        }
+        // there is always a tab to draw into, files of lost tabs join it
+        if self.plot_tabs.all().is_empty() {
+            self.plot_tabs = PlotTabs::default();
+        }
+        // loads that were running when the session was saved are lost
+        for file_entry in self.folders.iter_mut().flat_map(|folder| &mut folder.files) {
+            file_entry.cancel_loading();
+        }
         Ok(())
     }
 
@@ -244,6 +1221,40 @@ impl App {
                     .min_scrolled_height(800.0)
                     .show(ui, |ui| self.file_tree_ui(ui));
             });
+            menu_button(ui, "Edit", |ui| {
+                let undo = egui::Button::new("Undo").shortcut_text(ui.ctx().format_shortcut(
+                    &egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z),
+                ));
+                if ui.add_enabled(!self.undo_stack.is_empty(), undo).clicked() {
+                    self.undo();
+                    ui.close_menu();
+                }
+                if ui
+                    .button("Reset All Manipulations ...")
+                    .on_hover_text("Reset scale, offsets and transforms of every file")
+                    .clicked()
+                {
+                    self.confirm_reset = true;
+                    ui.close_menu();
+                }
+            });
+            menu_button(ui, "Plot", |ui| {
+                if ui
+                    .button("Fit to Window")
+                    .on_hover_text("Show all plotted data (Home)")
+                    .clicked()
+                {
+                    self.fit_to_window();
+                    ui.close_menu();
+                }
+                ui.separator();
+                self.plot_options_ui(ui);
+                ui.separator();
+                self.plot_settings_clipboard_ui(ui);
+                ui.menu_button("Average", |ui| self.averages_ui(ui));
+                ui.menu_button("Annotations", |ui| self.annotations_ui(ui));
+            });
+            menu_button(ui, "Settings", |ui| self.settings_ui(ui));
             menu_button(ui, "Session", |ui| {
                 if ui.button("Save Session").clicked() {
                     self.save_state(None)
@@ -271,23 +1282,55 @@ impl App {
                         }
                     }
                 }
+                ui.separator();
+                if ui.button("Reload All Plotted").clicked() {
+                    self.reload_plotted();
+                }
             });
             menu_button(ui, "File Settings", |ui| {
                 ui.set_min_width(400.0);
+                ui.checkbox(&mut self.inspector.visible, "Show inspector")
+                    .on_hover_text(
+                        "List all plotted files with their scale and offsets in a side panel",
+                    );
+                ui.separator();
                 let mut files_plotted = false;
                 for folder in self.folders.iter_mut() {
                     for file_entry in folder.files.iter_mut() {
                         if !file_entry.is_plotted() {
                             continue; // only list files that are plotted
                         }
-                        ui.menu_button(file_entry.get_file_label_text(), |ui| {
-                            file_settings_menu(
-                                ui,
-                                file_entry,
-                                &folder.path,
-                                &mut self.copied_csvoptions,
-                                &mut self.errors,
-                            )
+                        ui.horizontal(|ui| {
+                            let mut pinned = self.pinned_files.contains(&file_entry.id);
+                            if ui
+                                .toggle_value(&mut pinned, "📌")
+                                .on_hover_text("Keep the settings open in a side panel")
+                                .changed()
+                            {
+                                if pinned {
+                                    self.pinned_files.push(file_entry.id);
+                                } else {
+                                    self.pinned_files.retain(|id| *id != file_entry.id);
+                                }
+                            }
+                            let set_active = ui
+                                .menu_button(file_entry.get_file_label_text(), |ui| {
+                                    file_settings_menu(
+                                        ui,
+                                        file_entry,
+                                        &folder.path,
+                                        &mut self.copied_csvoptions,
+                                        &mut self.slider_options,
+                                        &self.plot_tabs,
+                                        &mut self.errors,
+                                    )
+                                })
+                                .inner
+                                .flatten();
+                            if let Some(active) = set_active {
+                                self.queued_events
+                                    .push(Box::new(SetActive::new(file_entry.id, active)));
+                            }
                         });
                         if !files_plotted {
                             files_plotted = true;
@@ -298,24 +1341,382 @@ impl App {
                     ui.label("Settings for plotted files will appear here.");
                 }
             });
-            if ui.button("Save Plot").clicked() {
-                if let Err(msg) = self.save_svg() {
-                    self.errors.push(msg);
-                };
-            }
+            menu_button(ui, "Export", |ui| {
+                if ui.button("Save Plot").clicked() {
+                    if let Err(msg) = self.save_svg() {
+                        self.errors.push(msg);
+                    };
+                }
+                if ui.button("Save Plot as PDF").clicked() {
+                    if let Err(msg) = self.save_pdf() {
+                        self.errors.push(msg);
+                    }
+                }
+                if ui.button("Copy SVG").clicked() {
+                    match self.svg_string() {
+                        Ok(svg) => {
+                            // eframe hands copied text to the system clipboard
+                            ui.output_mut(|o| o.copied_text = svg);
+                            ui.close_menu();
+                        }
+                        Err(msg) => self.errors.push(msg),
+                    }
+                }
+                if ui
+                    .button("Copy Visible Data")
+                    .on_hover_text("Copy the data within the x-range of the plot as a table")
+                    .clicked()
+                {
+                    if let Some(table) = self.visible_data_table() {
+                        ui.output_mut(|o| o.copied_text = table);
+                        ui.close_menu();
+                    }
+                }
+                if ui
+                    .button("Export Legend")
+                    .on_hover_text("Save only the labels and colors of the plotted series")
+                    .clicked()
+                {
+                    if let Err(msg) = self.save_legend() {
+                        self.errors.push(msg);
+                    }
+                }
+                if ui.button("Batch Export ...").clicked() {
+                    self.batch_export_svg();
+                }
+                ui.menu_button("Style", |ui| self.export_style_ui(ui));
+            });
+            menu_button(ui, "About", |ui| {
+                let info = build_info();
+                ui.label(&info);
+                if ui.button("Copy").clicked() {
+                    ui.output_mut(|o| o.copied_text = info);
+                    ui.close_menu();
+                }
+            });
         })
     }
 
+    fn confirm_reset_ui(&mut self, ctx: &egui::Context) {
+        if !self.confirm_reset {
+            return;
+        }
+        egui::Window::new("Reset All Manipulations?")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(
+                    "Scale, offsets, transforms, x-range, y-clamp, linear fit, \
+                     original curve, gaps and steps of all files in all folders \
+                     are reset, this can be undone with Edit > Undo.",
+                );
+                ui.horizontal(|ui| {
+                    if ui.button("Reset").clicked() {
+                        self.queued_events.push(Box::new(ResetManipulations::new(
+                            &self.manipulation_defaults,
+                        )));
+                        self.confirm_reset = false;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.confirm_reset = false;
+                    }
+                });
+            });
+    }
+
+    /// all plotted files with color, visibility and manipulations in a side
+    /// panel, flatter than the "File Settings" menu
+    fn inspector_ui(&mut self, ctx: &egui::Context) {
+        if !self.inspector.visible {
+            return;
+        }
+        let panel = egui::SidePanel::right("Inspector")
+            .resizable(true)
+            .default_width(self.inspector.width)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.strong("Plotted files");
+                    if ui
+                        .small_button("Hide")
+                        .on_hover_text("Show it again from the File Settings menu")
+                        .clicked()
+                    {
+                        self.inspector.visible = false;
+                    }
+                });
+                ui.separator();
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    ui.spacing_mut().text_edit_width = INSPECTOR_FIELD_WIDTH;
+                    let mut files_plotted = false;
+                    for folder in self.folders.iter_mut() {
+                        for file_entry in folder.files.iter_mut() {
+                            if !file_entry.is_plotted() {
+                                continue;
+                            }
+                            files_plotted = true;
+                            // widgets of different files must not share their ids
+                            ui.push_id(file_entry.id, |ui| {
+                                ui.horizontal(|ui| {
+                                    ui.color_edit_button_srgba(&mut file_entry.color);
+                                    ui.toggle_value(&mut file_entry.visible, "👁")
+                                        .on_hover_text("Show or hide in the plot");
+                                    if ui
+                                        .selectable_label(file_entry.is_active(), "A")
+                                        .on_hover_text(
+                                            "Make active, drag manipulations apply to active files",
+                                        )
+                                        .clicked()
+                                    {
+                                        self.queued_events.push(Box::new(SetActive::new(
+                                            file_entry.id,
+                                            !file_entry.is_active(),
+                                        )));
+                                    }
+                                    let set_active = ui
+                                        .menu_button("⚙", |ui| {
+                                            file_settings_menu(
+                                                ui,
+                                                file_entry,
+                                                &folder.path,
+                                                &mut self.copied_csvoptions,
+                                                &mut self.slider_options,
+                                                &self.plot_tabs,
+                                                &mut self.errors,
+                                            )
+                                        })
+                                        .inner
+                                        .flatten();
+                                    if let Some(active) = set_active {
+                                        self.queued_events
+                                            .push(Box::new(SetActive::new(file_entry.id, active)));
+                                    }
+                                    if ui
+                                        .small_button("x")
+                                        .on_hover_text("Remove from the plot")
+                                        .clicked()
+                                    {
+                                        file_entry.hide();
+                                    }
+                                    ui.add(
+                                        egui::Label::new(file_entry.get_file_label_text())
+                                            .truncate(),
+                                    );
+                                });
+                                egui::Grid::new("manipulations")
+                                    .num_columns(2)
+                                    .show(ui, |ui| {
+                                        ui.label("Scale");
+                                        float_text_field(ui, &mut file_entry.scale);
+                                        ui.end_row();
+                                        ui.label("y-Offset");
+                                        float_text_field(ui, &mut file_entry.offset);
+                                        ui.end_row();
+                                        ui.label("x-Offset");
+                                        float_text_field(ui, &mut file_entry.xoffset);
+                                        ui.end_row();
+                                    });
+                            });
+                            ui.separator();
+                        }
+                    }
+                    if !files_plotted {
+                        ui.label("Plotted files will appear here.");
+                    }
+                });
+            });
+        self.inspector.width = panel.response.rect.width();
+    }
+
+    /// settings of pinned files, which stay open while the plot is manipulated
+    fn pinned_settings_ui(&mut self, ctx: &egui::Context) {
+        // forget files that are gone, e.g. because their folder was removed
+        let folders = &self.folders;
+        self.pinned_files.retain(|id| {
+            folders
+                .iter()
+                .flat_map(|folder| &folder.files)
+                .any(|file_entry| file_entry.id == *id)
+        });
+        if self.pinned_files.is_empty() {
+            return;
+        }
+        egui::SidePanel::right("Pinned Settings")
+            .resizable(true)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    let mut unpinned = None;
+                    for folder in self.folders.iter_mut() {
+                        for file_entry in folder.files.iter_mut() {
+                            if !self.pinned_files.contains(&file_entry.id) {
+                                continue;
+                            }
+                            // widgets of different files must not share their ids
+                            ui.push_id(file_entry.id, |ui| {
+                                ui.horizontal(|ui| {
+                                    ui.label(file_entry.get_file_label_text().strong());
+                                    if ui.small_button("Unpin").clicked() {
+                                        unpinned = Some(file_entry.id);
+                                    }
+                                });
+                                if let Some(active) = file_settings_menu(
+                                    ui,
+                                    file_entry,
+                                    &folder.path,
+                                    &mut self.copied_csvoptions,
+                                    &mut self.slider_options,
+                                    &self.plot_tabs,
+                                    &mut self.errors,
+                                ) {
+                                    self.queued_events
+                                        .push(Box::new(SetActive::new(file_entry.id, active)));
+                                }
+                            });
+                            ui.separator();
+                        }
+                    }
+                    if let Some(id) = unpinned {
+                        self.pinned_files.retain(|pinned| *pinned != id);
+                    }
+                });
+            });
+    }
+
+    fn settings_ui(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(
+            &mut self.manipulate_primary_only,
+            "Manipulate primary file only",
+        )
+        .on_hover_text(
+            "Drag gestures only scale/offset the file that was activated last,\n\
+             instead of all active files.",
+        );
+        if self.manipulate_primary_only {
+            let primary = self
+                .folders
+                .iter()
+                .flat_map(|folder| &folder.files)
+                .find(|file_entry| Some(file_entry.id) == self.primary_file)
+                .map(|file_entry| file_entry.filename.as_str())
+                .unwrap_or("none (right-click a file to activate it)");
+            ui.label(format!("Primary file: {}", primary));
+        }
+        ui.menu_button("Key Bindings", |ui| self.key_bindings.ui(ui));
+        ui.menu_button("Arrow Key Steps", |ui| self.nudge_step.ui(ui));
+        ui.menu_button("Automatic Colors", |ui| {
+            self.color_cycle.ui(ui, &mut self.errors)
+        });
+        ui.horizontal(|ui| {
+            ui.label("Separator when copying values (C)");
+            egui::ComboBox::from_id_source("copy_separator")
+                .selected_text(self.copy_separator.label())
+                .show_ui(ui, |ui| {
+                    for separator in CopySeparator::ALL {
+                        ui.selectable_value(&mut self.copy_separator, separator, separator.label());
+                    }
+                });
+        })
+        .response
+        .on_hover_text(
+            "Press C over the plot to copy the nearest point of the hovered file,\n\
+             or the cursor coordinates, to the clipboard.",
+        );
+        ui.menu_button("Defaults for New Files", |ui| {
+            ui.label("Scale and offsets of files in folders opened from now on:");
+            let defaults = &mut self.manipulation_defaults;
+            egui::Grid::new("manipulation_defaults")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    for (label, value) in [
+                        ("Scale", &mut defaults.scale),
+                        ("y-Offset", &mut defaults.offset),
+                        ("x-Offset", &mut defaults.xoffset),
+                    ] {
+                        ui.label(label);
+                        float_text_field(ui, value);
+                        ui.end_row();
+                    }
+                });
+            if ui.button("Reset Defaults").clicked() {
+                *defaults = ManipulationDefaults::default();
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.auto_stack.enabled, "Stack plotted files by")
+                    .on_hover_text(
+                        "Give every newly plotted file a y-offset one step above the previous one",
+                    );
+                ui.add_enabled(
+                    self.auto_stack.enabled,
+                    egui::DragValue::new(&mut self.auto_stack.step).speed(0.1),
+                );
+            });
+        });
+    }
+
+    /// give files plotted since the last frame the next offset of the
+    /// stack, if `auto_stack` is enabled, files plotted again keep the offset
+    /// they got before
+    fn auto_stack_plotted(&mut self) {
+        let plotted: Vec<usize> = self
+            .folders
+            .iter()
+            .flat_map(|folder| &folder.files)
+            .filter(|file_entry| file_entry.is_plotted())
+            .map(|file_entry| file_entry.id)
+            .collect();
+        let Some(previous) = self.plotted_files.replace(plotted.clone()) else {
+            return;
+        };
+        if !self.auto_stack.enabled {
+            return;
+        }
+        let mut num_stacked = plotted.iter().filter(|id| previous.contains(id)).count();
+        let default_offset = self.manipulation_defaults.offset.parse();
+        for file_entry in self.folders.iter_mut().flat_map(|folder| &mut folder.files) {
+            if file_entry.is_plotted()
+                && !previous.contains(&file_entry.id)
+                && !self.auto_stack.stacked.contains(&file_entry.id)
+                && file_entry.offset.parse() == default_offset
+            {
+                file_entry.offset.input = format!("{}", self.auto_stack.step * num_stacked as f64);
+                self.auto_stack.stacked.push(file_entry.id);
+                num_stacked += 1;
+            }
+        }
+    }
+
+    /// move the current search phrase to the front of the history
+    fn remember_search_phrase(&mut self) {
+        let phrase = self.search_phrase.trim();
+        if phrase.is_empty() {
+            return;
+        }
+        let phrase = phrase.to_string();
+        self.search_history.retain(|previous| *previous != phrase);
+        self.search_history.insert(0, phrase);
+        self.search_history.truncate(MAX_SEARCH_HISTORY);
+    }
+
     fn file_tree_ui(&mut self, ui: &mut egui::Ui) {
         if ui.button("Open Folder").clicked() {
             for folder in rfd::FileDialog::new().pick_folders().unwrap_or_default() {
-                let files = get_file_entries(&folder, &mut self.id_counter);
-                self.folders.push(Folder {
-                    path: folder,
-                    files,
-                    expanded: true,
-                    to_be_deleted: false,
-                })
+                // opening a folder twice would reset the state of its files
+                if let Some(existing) = self.folders.iter_mut().find(|f| f.path == folder) {
+                    existing.expanded = true;
+                    self.errors.push(format!(
+                        "WARNING: folder {} is already open.",
+                        folder.to_string_lossy()
+                    ));
+                    continue;
+                }
+                let files = get_file_entries(
+                    &folder,
+                    &mut self.id_counter,
+                    &self.manipulation_defaults,
+                    &mut self.errors,
+                );
+                self.folders.push(Folder::new(folder, files))
             }
         }
 
@@ -324,10 +1725,49 @@ impl App {
             return;
         }
 
+        self.goto_file_ui(ui);
+
         let lab = ui.label("Filter:");
         let prev_search_phrase = self.search_phrase.clone();
-        ui.text_edit_singleline(&mut self.search_phrase)
-            .labelled_by(lab.id);
+        ui.horizontal(|ui| {
+            let response = ui
+                .text_edit_singleline(&mut self.search_phrase)
+                .labelled_by(lab.id);
+            // a phrase counts as used once the user is done typing it
+            if response.lost_focus() {
+                self.remember_search_phrase();
+            }
+            ui.add_enabled_ui(!self.search_history.is_empty(), |ui| {
+                ui.menu_button("🕓", |ui| {
+                    for phrase in &self.search_history {
+                        if ui.button(phrase).clicked() {
+                            self.search_phrase = phrase.clone();
+                            ui.close_menu();
+                        }
+                    }
+                })
+                .response
+                .on_hover_text("Recent filters");
+            });
+        });
+        ui.horizontal(|ui| {
+            ui.label("Sort by");
+            egui::ComboBox::from_id_source("file_sort")
+                .selected_text(self.file_sort.label())
+                .show_ui(ui, |ui| {
+                    for sort in FileSort::ALL {
+                        ui.selectable_value(&mut self.file_sort, sort, sort.label());
+                    }
+                });
+            ui.label("Labels");
+            egui::ComboBox::from_id_source("file_labels")
+                .selected_text(self.file_labels.label())
+                .show_ui(ui, |ui| {
+                    for labels in FileLabels::ALL {
+                        ui.selectable_value(&mut self.file_labels, labels, labels.label());
+                    }
+                });
+        });
         // if search phrase has changed, release previously plotted file entries
         // from being shown
         if prev_search_phrase != self.search_phrase {
@@ -335,81 +1775,43 @@ impl App {
                 file_entry.search_phrase_changed()
             }
         }
+        self.file_tree_keyboard_navigation(ui);
         self.list_folders(ui);
         // delete folders that were marked to be deleted
         self.delete_folders();
     }
+}
 
-    fn save_svg(&self) -> Result<(), String> {
-        use plotters::prelude::*;
-
-        let filepath = if let Some(path) = rfd::FileDialog::new().save_file() {
-            path
-        } else {
-            return Err("ERROR: selected path unvalid.".to_string());
-        };
-        let root = SVGBackend::new(&filepath, (1024, 768)).into_drawing_area();
-        // let font: FontDesc = ("sans-serif", 20.0).into();
-
-        root.fill(&WHITE)
-            .err_to_string("ERROR: to prepare canvas for SVG export")?;
-
-        let mut chart = ChartBuilder::on(&root)
-            .margin(20u32)
-            // .caption(format!("y=x^{}", 2), font)
-            .x_label_area_size(30u32)
-            .y_label_area_size(30u32)
-            .build_cartesian_2d(
-                self.plot_dims.x0..self.plot_dims.x1,
-                self.plot_dims.y0..self.plot_dims.y1,
-            )
-            .err_to_string("ERROR: unable to build chart for SVG export")?;
-
-        chart
-            .configure_mesh()
-            .x_labels(3)
-            .y_labels(3)
-            .draw()
-            .err_to_string("ERROR: unable to prepare labels for SVG export")?;
-
-        for file_entry in self.folders.iter().flat_map(|folder| &folder.files) {
-            if !file_entry.is_plotted() || file_entry.color == Color32::TRANSPARENT {
-                continue;
-            }
-            let scale = file_entry.scale.parse().unwrap_or(1.0);
-            let offset = file_entry.offset.parse().unwrap_or(0.0);
-            let xoffset = file_entry.xoffset.parse().unwrap_or(0.0);
-            let color = {
-                let (r, g, b, a) = file_entry.color.to_tuple();
-                RGBAColor(r, g, b, a as f64 / 255.).stroke_width(2)
-            };
+// text input of a `FloatInput`, framed red while the input is not a number
+fn float_text_field(ui: &mut egui::Ui, value: &mut FloatInput) -> egui::Response {
+    if value.parse().is_some() {
+        return ui.text_edit_singleline(&mut value.input);
+    }
+    ui.scope(|ui| {
+        let stroke = egui::Stroke::new(1.0, ui.visuals().error_fg_color);
+        let visuals = ui.visuals_mut();
+        visuals.widgets.inactive.bg_stroke = stroke;
+        visuals.widgets.hovered.bg_stroke = stroke;
+        visuals.selection.stroke = stroke;
+        ui.text_edit_singleline(&mut value.input)
+            .on_hover_text("Not a number, the default is used")
+    })
+    .inner
+}
 
-            chart
-                .draw_series(LineSeries::new(
-                    file_entry
-                        .data_file
-                        .data
-                        .iter()
-                        .map(|[x, y]| (*x + xoffset, *y * scale + offset))
-                        .map(|(x, y)| (x as f32, y as f32)),
-                    color,
-                ))
-                .err_to_string("ERROR: unable to draw data for SVG export")?
-                .label(&file_entry.filename)
-                .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
-        }
-
-        chart
-            .configure_series_labels()
-            .background_style(WHITE.mix(0.8))
-            .border_style(BLACK)
-            .position(SeriesLabelPosition::UpperRight)
-            .draw()
-            .err_to_string("ERROR: unable to configure labels for SVG export")?;
-
-        root.present()
-            .err_to_string("ERROR: unable to write SVG output")?;
-        Ok(())
+// drag slider operating on the parsed value of a `FloatInput`, which is only
+// shown while the input holds a valid number
+fn float_drag_field(ui: &mut egui::Ui, value: &mut FloatInput, options: &SliderOptions) {
+    let Some(mut number) = value.parse() else {
+        return;
+    };
+    let drag_value = egui::DragValue::new(&mut number)
+        .speed(options.step)
+        .range(options.min..=options.max)
+        // typed values outside of the range are kept, only dragging is limited
+        .clamp_to_range(false);
+    if ui.add(drag_value).changed() {
+        value.input = format!("{}", number);
     }
 }
 
@@ -422,7 +1824,145 @@ fn integer_edit_field(ui: &mut egui::Ui, value: &mut usize) -> egui::Response {
     res
 }
 
+// cargo does not expose the versions of dependencies, keep in sync with Cargo.toml
+const EGUI_VERSION: &str = "0.28.1";
+
+/// version information to include in bug reports
+fn build_info() -> String {
+    format!(
+        "plotme {}\negui/eframe {}\nOS: {} ({})",
+        env!("CARGO_PKG_VERSION"),
+        EGUI_VERSION,
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+    )
+}
+
+// number of search phrases kept in the history
+const MAX_SEARCH_HISTORY: usize = 10;
+
+// number of log entries kept for copying after they are no longer shown
+const MAX_LOG_HISTORY: usize = 1000;
+
+fn default_max_log_entries() -> usize {
+    10
+}
+
 fn default_config_path() -> Result<PathBuf, std::env::VarError> {
     let home_path = std::env::var("HOME")?;
     Ok(PathBuf::from(home_path).join(".plotme.json"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::file_entry::FileEntryState;
+
+    #[test]
+    fn grid_color_of_old_sessions_is_kept() {
+        // sessions saved before the grid options had the color in the export style
+        let mut session = serde_json::to_value(App::default()).unwrap();
+        session["export_style"]["mesh"] = serde_json::json!([10, 20, 30, 255]);
+        session["plot_options"]
+            .as_object_mut()
+            .unwrap()
+            .remove("grid");
+        let path = std::env::temp_dir().join("plotme_old_session.json");
+        fs::write(&path, session.to_string()).unwrap();
+        let mut app = App::default();
+        let loaded = app.load_state(Some(path.clone()));
+        fs::remove_file(path).unwrap();
+        loaded.unwrap();
+        assert_eq!(
+            app.plot_options.grid.color,
+            egui::Color32::from_rgb(10, 20, 30)
+        );
+        assert!(app.export_style.mesh.is_none());
+        // the color is only written in its new place
+        let saved = serde_json::to_value(&app).unwrap();
+        assert!(saved["export_style"].get("mesh").is_none());
+    }
+
+    #[test]
+    fn session_without_tabs_gets_a_tab() {
+        let mut session = serde_json::to_value(App::default()).unwrap();
+        session["plot_tabs"]["tabs"] = serde_json::json!([]);
+        let path = std::env::temp_dir().join("plotme_session_without_tabs.json");
+        fs::write(&path, session.to_string()).unwrap();
+        let mut app = App::default();
+        let loaded = app.load_state(Some(path.clone()));
+        fs::remove_file(path).unwrap();
+        loaded.unwrap();
+        assert_eq!(app.plot_tabs.all().len(), 1);
+        assert_eq!(app.plot_tabs.active().id, 0);
+    }
+
+    #[test]
+    fn replotted_files_keep_their_stack_offset() {
+        let dir = std::env::temp_dir().join("plotme_replotted_files");
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["0.csv", "1.csv"] {
+            fs::write(dir.join(name), "").unwrap();
+        }
+        let mut files =
+            get_file_entries(&dir, &mut 0, &ManipulationDefaults::default(), &mut vec![]);
+        fs::remove_dir_all(dir).unwrap();
+        for file_entry in &mut files {
+            file_entry.data_file.data = vec![[0.0, 0.0], [1.0, 1.0]];
+        }
+        let mut app = App {
+            folders: vec![Folder::new("data".into(), files)],
+            auto_stack: AutoStack {
+                enabled: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let plot = |app: &mut App, index: usize, plotted: bool| {
+            let file_entry = &mut app.folders[0].files[index];
+            match plotted {
+                true => file_entry.restore_state(FileEntryState::Plotted),
+                false => file_entry.hide(),
+            }
+            app.auto_stack_plotted();
+        };
+        let offsets = |app: &App| -> Vec<String> {
+            app.folders[0]
+                .files
+                .iter()
+                .map(|file_entry| file_entry.offset.input.clone())
+                .collect()
+        };
+        app.auto_stack_plotted();
+        plot(&mut app, 0, true);
+        plot(&mut app, 1, true);
+        assert_eq!(offsets(&app), ["0", "1"]);
+        // plotting a file again does not move it to the top of the stack
+        plot(&mut app, 0, false);
+        plot(&mut app, 0, true);
+        assert_eq!(offsets(&app), ["0", "1"]);
+        plot(&mut app, 1, false);
+        plot(&mut app, 1, true);
+        assert_eq!(offsets(&app), ["0", "1"]);
+    }
+
+    #[test]
+    fn copied_log_includes_trimmed_entries() {
+        let mut app = App {
+            max_log_entries: 2,
+            ..Default::default()
+        };
+        app.errors = (0..5).map(|i| format!("INFO: {i}")).collect();
+        app.trim_log();
+        assert_eq!(app.errors, ["INFO: 3", "INFO: 4"]);
+        assert_eq!(
+            app.log_text(),
+            "INFO: 0\nINFO: 1\nINFO: 2\nINFO: 3\nINFO: 4"
+        );
+        app.errors
+            .extend((0..MAX_LOG_HISTORY).map(|i| format!("INFO: {i}")));
+        app.trim_log();
+        assert_eq!(app.log_history.len(), MAX_LOG_HISTORY);
+        assert!(app.log_text().starts_with("INFO: 3\nINFO: 4\nINFO: 0\n"));
+    }
+}
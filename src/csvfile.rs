@@ -1,4 +1,8 @@
-use std::path::{Path, PathBuf};
+use std::{
+    collections::VecDeque,
+    io::BufRead,
+    path::{Path, PathBuf},
+};
 
 use serde::{Deserialize, Serialize};
 
@@ -11,7 +15,164 @@ pub struct CSVFile {
     pub xcol: usize,
     pub ycol: usize,
     pub skip_header: usize,
+    /// number of physical lines dropped from the end of the file, trailing
+    /// blank lines (including the empty one after a final line break) are
+    /// not counted
     pub skip_footer: usize,
+    /// use the record index as x instead of reading `xcol`
+    #[serde(default)]
+    pub x_is_index: bool,
+    /// remove everything from the comment character to the end of the line
+    #[serde(default)]
+    pub strip_inline_comments: bool,
+    /// lines starting with this prefix, like `//` or `%`, are skipped in
+    /// addition to those starting with `comment_char`
+    #[serde(default)]
+    pub comment_prefix: String,
+    /// drop lines that only hold whitespace, e.g. between data blocks
+    #[serde(default)]
+    pub skip_blank_lines: bool,
+    /// keep only every `stride`-th record
+    #[serde(default = "default_stride")]
+    pub stride: usize,
+    #[serde(default)]
+    pub non_finite: NonFinitePolicy,
+    /// plot column `param_ycol` against column `param_xcol` as a parametric
+    /// curve, in the order of the records
+    #[serde(default)]
+    pub parametric: bool,
+    #[serde(default)]
+    pub param_xcol: usize,
+    #[serde(default = "default_param_ycol")]
+    pub param_ycol: usize,
+    /// on the next load, use the first increasing numeric column as x and
+    /// the next numeric column as y
+    #[serde(default)]
+    pub auto_columns: bool,
+    #[serde(default)]
+    pub number_format: NumberFormat,
+    /// regular expression with the named groups `x` and `y`, if it is not
+    /// empty every line is matched with it instead of being split into fields
+    #[serde(default)]
+    pub field_pattern: String,
+    /// read all rows, even of files above `SAMPLE_THRESHOLD_BYTES`
+    #[serde(default)]
+    pub load_full: bool,
+    /// remember the row every point was read from, to show it on hover
+    #[serde(default)]
+    pub keep_rows: bool,
+    /// 1-based line of the file of every point of `data` if `keep_rows` is
+    /// set, empty otherwise, a result of parsing like `data`
+    #[serde(default)]
+    pub rows: Vec<usize>,
+    /// the additional stride large files were sampled with, `None` if
+    /// `data` holds every row, like `data` this is a result of parsing
+    #[serde(default)]
+    pub sampled: Option<usize>,
+    /// how the lines of the file were handled, a result of parsing as well
+    #[serde(default)]
+    pub parse_stats: ParseStats,
+}
+
+/// line counts of the last parse of a file
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+pub struct ParseStats {
+    /// lines of the file, without the skipped header and footer
+    pub total: usize,
+    /// lines starting with the comment character or prefix
+    pub comments: usize,
+    /// rows read as data points, including non-finite y-values
+    pub parsed: usize,
+    /// rows that could not be read or parsed, or did not match the field pattern
+    pub failed: usize,
+}
+
+impl ParseStats {
+    pub fn summary(&self) -> String {
+        format!(
+            "{} lines: {} parsed, {} comments, {} failed",
+            self.total, self.parsed, self.comments, self.failed
+        )
+    }
+}
+
+/// files larger than this are sampled with a stride, unless `load_full` is set
+pub const SAMPLE_THRESHOLD_BYTES: usize = 100_000_000;
+
+fn default_param_ycol() -> usize {
+    2
+}
+
+/// what to do with y-values like `nan` or `inf` that parse successfully
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NonFinitePolicy {
+    Keep,
+    #[default]
+    Drop,
+    /// linear interpolation between the neighboring finite points
+    Interpolate,
+}
+
+impl NonFinitePolicy {
+    pub const ALL: [NonFinitePolicy; 3] = [Self::Keep, Self::Drop, Self::Interpolate];
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Keep => "Keep",
+            Self::Drop => "Drop",
+            Self::Interpolate => "Interpolate",
+        }
+    }
+}
+
+/// decimal and thousands separators of the numbers in a file
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberFormat {
+    /// `1234.56`
+    #[default]
+    Point,
+    /// `1,234.56`
+    PointWithThousandsComma,
+    /// `1234,56`
+    Comma,
+    /// `1.234,56`
+    CommaWithThousandsPoint,
+}
+
+impl NumberFormat {
+    pub const ALL: [NumberFormat; 4] = [
+        Self::Point,
+        Self::PointWithThousandsComma,
+        Self::Comma,
+        Self::CommaWithThousandsPoint,
+    ];
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Point => "1234.56",
+            Self::PointWithThousandsComma => "1,234.56",
+            Self::Comma => "1234,56",
+            Self::CommaWithThousandsPoint => "1.234,56",
+        }
+    }
+    /// `field` as a number, after removing the thousands separator and
+    /// replacing the decimal separator by a point
+    pub fn parse(&self, field: &str) -> Result<f64, std::num::ParseFloatError> {
+        let (decimal, thousands) = match self {
+            Self::Point => return field.parse(),
+            Self::PointWithThousandsComma => ('.', ','),
+            Self::Comma => (',', '\0'),
+            Self::CommaWithThousandsPoint => (',', '.'),
+        };
+        field
+            .chars()
+            .filter(|ch| *ch != thousands)
+            .map(|ch| if ch == decimal { '.' } else { ch })
+            .collect::<String>()
+            .parse()
+    }
+}
+
+fn default_stride() -> usize {
+    1
 }
 
 impl Default for CSVFile {
@@ -25,102 +186,1176 @@ impl Default for CSVFile {
             ycol: 1,
             skip_header: 0,
             skip_footer: 0,
+            x_is_index: false,
+            strip_inline_comments: false,
+            comment_prefix: String::new(),
+            skip_blank_lines: false,
+            stride: default_stride(),
+            non_finite: NonFinitePolicy::default(),
+            parametric: false,
+            param_xcol: 1,
+            param_ycol: default_param_ycol(),
+            auto_columns: false,
+            number_format: NumberFormat::default(),
+            field_pattern: String::new(),
+            load_full: false,
+            keep_rows: false,
+            rows: vec![],
+            sampled: None,
+            parse_stats: ParseStats::default(),
         }
     }
 }
 
-impl CSVFile {
-    pub fn new(
-        filepath: PathBuf,
+/// problem found while parsing a file, most of them only affect single records
+pub enum ParseWarning {
+    /// the file could not be read at all
+    Unreadable(String),
+    /// the csv reader rejected a record
+    InvalidRecord { row: usize, message: String },
+    /// a column holds something that is not a number
+    InvalidValue {
+        row: usize,
+        axis: char,
+        col: usize,
+        message: String,
+    },
+    /// the record has none of the columns, or neither one parses
+    MissingColumns {
+        row: usize,
         xcol: usize,
         ycol: usize,
-        delimiter: u8,
-        comment_char: u8,
-        skip_header: usize,
-        skip_footer: usize,
-        error_log: &mut Vec<String>,
-    ) -> Option<Self> {
-        let rdr = csv::ReaderBuilder::new()
-            .comment(Some(comment_char))
-            .delimiter(delimiter)
-            .from_path(filepath.clone())
-            .map_err(|err| {
-                error_log.push(format!(
-                    "ERROR: could not read CSV file {filepath:?}: {}",
-                    err
-                ))
-            });
-        if rdr.is_err() {
-            return None;
+    },
+    /// no increasing x-column was found, the configured columns were used
+    NoAutoColumns { xcol: usize, ycol: usize },
+    /// the field pattern is no valid regular expression or lacks a group
+    InvalidPattern(String),
+    /// lines that do not match the field pattern, e.g. headers
+    UnmatchedLines { count: usize },
+    /// the file exceeds `SAMPLE_THRESHOLD_BYTES`, only every `stride`-th row was read
+    Sampled { stride: usize },
+    /// y-values like `nan` or `inf`, handled according to the `NonFinitePolicy`
+    NonFinite {
+        count: usize,
+        policy: NonFinitePolicy,
+    },
+}
+
+impl ParseWarning {
+    /// entry for the error log of the GUI
+    pub fn log_message(&self, filepath: &Path) -> String {
+        match self {
+            Self::Unreadable(err) => {
+                format!("ERROR: could not read CSV file {filepath:?}: {err}")
+            }
+            Self::InvalidRecord { row, message } => {
+                format!("WARNING: could not parse row {row} of file {filepath:?}: {message}")
+            }
+            Self::InvalidValue {
+                row,
+                axis,
+                col,
+                message,
+            } => format!(
+                "WARNING: {axis}-column {col} could not be parsed in entry {row} for file {filepath:?}: {message}"
+            ),
+            Self::MissingColumns { row, xcol, ycol } => format!(
+                "WARNING: could not parse columns {xcol}, {ycol} in entry {row} for file {filepath:?}"
+            ),
+            Self::NoAutoColumns { xcol, ycol } => format!(
+                "INFO: no increasing numeric column found in file {filepath:?}, using columns {xcol}, {ycol}"
+            ),
+            Self::InvalidPattern(err) => {
+                format!("ERROR: invalid field pattern for file {filepath:?}: {err}")
+            }
+            Self::UnmatchedLines { count } => format!(
+                "INFO: {count} lines of file {filepath:?} do not match the field pattern and were skipped"
+            ),
+            Self::Sampled { stride } => format!(
+                "INFO: file {filepath:?} is large, only one in {stride} rows was loaded, \
+                 enable \"Load full file\" for the complete data (e.g. before exporting)"
+            ),
+            Self::NonFinite { count, policy } => {
+                let action = match policy {
+                    NonFinitePolicy::Keep => "kept",
+                    NonFinitePolicy::Drop => "dropped",
+                    NonFinitePolicy::Interpolate => "interpolated",
+                };
+                format!(
+                    "WARNING: {count} non-finite y-values in file {filepath:?} were {action}"
+                )
+            }
         }
+    }
+}
+
+impl CSVFile {
+    /// read `filepath` with the parse options of `options` and log all
+    /// warnings to `error_log`
+    pub fn new(filepath: PathBuf, options: &CSVFile, error_log: &mut Vec<String>) -> Option<Self> {
+        let (csvfile, warnings) = Self::parse(filepath.clone(), options);
+        error_log.extend(
+            warnings
+                .iter()
+                .map(|warning| warning.log_message(&filepath)),
+        );
+        csvfile
+    }
+
+    /// read `filepath` with the parse options of `options`, no file is
+    /// returned if no data could be read
+    pub fn parse(filepath: PathBuf, options: &CSVFile) -> (Option<Self>, Vec<ParseWarning>) {
+        let size = std::fs::metadata(&filepath).map_or(0, |metadata| metadata.len() as usize);
+        let read = match sample_stride(size, options) {
+            // only the sampled lines are held in memory
+            Some(stride) => std::fs::File::open(&filepath)
+                .and_then(|file| sample_lines(std::io::BufReader::new(file), options, stride))
+                .map(|(sampled, stats, lines)| {
+                    Self::parse_prepared(filepath, &sampled, stats, lines, Some(stride), options)
+                }),
+            None => std::fs::read(&filepath).map(|raw| Self::parse_bytes(filepath, &raw, options)),
+        };
+        read.unwrap_or_else(|err| (None, vec![ParseWarning::Unreadable(err.to_string())]))
+    }
+
+    /// like `parse`, but read the data from `reader`, e.g. stdin,
+    /// `filepath` only names the source in the parsed file
+    pub fn parse_reader(
+        mut reader: impl std::io::Read,
+        filepath: PathBuf,
+        options: &CSVFile,
+    ) -> (Option<Self>, Vec<ParseWarning>) {
+        let mut raw = Vec::new();
+        match reader.read_to_end(&mut raw) {
+            Ok(_) => Self::parse_bytes(filepath, &raw, options),
+            Err(err) => (None, vec![ParseWarning::Unreadable(err.to_string())]),
+        }
+    }
+
+    /// the parse core shared by `parse` and `parse_reader`
+    fn parse_bytes(
+        filepath: PathBuf,
+        raw: &[u8],
+        options: &CSVFile,
+    ) -> (Option<Self>, Vec<ParseWarning>) {
+        if let Some(stride) = sample_stride(raw.len(), options) {
+            return match sample_lines(raw, options, stride) {
+                Ok((sampled, stats, lines)) => {
+                    Self::parse_prepared(filepath, &sampled, stats, lines, Some(stride), options)
+                }
+                Err(err) => (None, vec![ParseWarning::Unreadable(err.to_string())]),
+            };
+        }
+        let raw = drop_footer(strip_bom(raw), options.skip_footer);
+        let raw = drop_header(raw, options.skip_header);
+        let mut lines = LineMap {
+            offset: options.skip_header,
+            lines: vec![],
+        };
+        let stats = count_lines(raw, options.comment_char, options.comment_prefix.as_bytes());
+
+        let stripped;
+        let bytes = if options.strip_inline_comments {
+            // custom parse path: remove trailing comments before the csv
+            // reader splits the fields
+            stripped = strip_inline_comments(raw, options.comment_char);
+            stripped.as_slice()
+        } else {
+            raw
+        };
+        let filtered;
+        let bytes = if !options.comment_prefix.is_empty() || options.skip_blank_lines {
+            filtered = filter_lines(
+                bytes,
+                options.comment_prefix.as_bytes(),
+                options.skip_blank_lines,
+                options.keep_rows.then_some(&mut lines.lines),
+            );
+            filtered.as_slice()
+        } else {
+            bytes
+        };
+        Self::parse_prepared(filepath, bytes, stats, lines, None, options)
+    }
+
+    /// read the points from `bytes`, which holds only the lines left after
+    /// skipping header and footer and, with `sampled`, only every
+    /// `sampled`-th record, `lines` tells where they are in the file
+    fn parse_prepared(
+        filepath: PathBuf,
+        bytes: &[u8],
+        mut stats: ParseStats,
+        lines: LineMap,
+        sampled: Option<usize>,
+        options: &CSVFile,
+    ) -> (Option<Self>, Vec<ParseWarning>) {
+        let mut warnings = Vec::new();
+        let mut builder = csv::ReaderBuilder::new();
+        builder
+            .comment(Some(options.comment_char))
+            .delimiter(options.delimiter)
+            // files from spreadsheet programs may pad fields or end lines
+            // with `\r`
+            .trim(csv::Trim::All);
 
-        let rdr = rdr.unwrap();
+        let mut options = options.options();
+        let use_pattern = !options.field_pattern.is_empty();
+        if options.auto_columns && !options.parametric && !use_pattern {
+            // the detected columns are kept for later loads
+            options.auto_columns = false;
+            match detect_columns(builder.from_reader(bytes), options.number_format) {
+                Some((xcol, ycol)) => {
+                    options.xcol = xcol;
+                    options.ycol = ycol;
+                    options.x_is_index = false;
+                }
+                None => warnings.push(ParseWarning::NoAutoColumns {
+                    xcol: options.xcol,
+                    ycol: options.ycol,
+                }),
+            }
+        }
 
-        let data = parse_rows(rdr, xcol, ycol, &filepath, error_log);
+        if let Some(stride) = sampled {
+            warnings.push(ParseWarning::Sampled { stride });
+        }
+        let mut rows = vec![];
+        let mut data = if use_pattern {
+            parse_lines(
+                bytes,
+                &options,
+                sampled.unwrap_or(1),
+                &lines,
+                &mut rows,
+                &mut warnings,
+                &mut stats,
+            )
+        } else {
+            parse_rows(
+                builder.from_reader(bytes),
+                &options,
+                sampled.unwrap_or(1),
+                RecordLines::new(bytes, options.comment_char, &lines),
+                &mut rows,
+                &mut warnings,
+                &mut stats,
+            )
+        };
+        stats.parsed = data.len();
+        handle_non_finite(&mut data, &mut rows, options.non_finite, &mut warnings);
         if data.is_empty() {
-            return None;
+            return (None, warnings);
         }
-        Some(CSVFile {
+        let csvfile = CSVFile {
             filepath,
             data,
+            rows,
+            sampled,
+            parse_stats: stats,
+            ..options
+        };
+        (Some(csvfile), warnings)
+    }
+
+    /// copy of the parse options, without path and data
+    pub fn options(&self) -> Self {
+        // destructure so that new options can not be forgotten here
+        let CSVFile {
+            filepath: _,
+            data: _,
             delimiter,
             comment_char,
             xcol,
             ycol,
             skip_header,
             skip_footer,
-        })
+            x_is_index,
+            strip_inline_comments,
+            comment_prefix,
+            skip_blank_lines,
+            stride,
+            non_finite,
+            parametric,
+            param_xcol,
+            param_ycol,
+            auto_columns,
+            number_format,
+            field_pattern,
+            load_full,
+            keep_rows,
+            rows: _,
+            sampled: _,
+            parse_stats: _,
+        } = self;
+        CSVFile {
+            filepath: "".into(),
+            data: vec![],
+            delimiter: *delimiter,
+            comment_char: *comment_char,
+            xcol: *xcol,
+            ycol: *ycol,
+            skip_header: *skip_header,
+            skip_footer: *skip_footer,
+            x_is_index: *x_is_index,
+            strip_inline_comments: *strip_inline_comments,
+            comment_prefix: comment_prefix.clone(),
+            skip_blank_lines: *skip_blank_lines,
+            stride: *stride,
+            non_finite: *non_finite,
+            parametric: *parametric,
+            param_xcol: *param_xcol,
+            param_ycol: *param_ycol,
+            auto_columns: *auto_columns,
+            number_format: *number_format,
+            field_pattern: field_pattern.clone(),
+            load_full: *load_full,
+            keep_rows: *keep_rows,
+            rows: vec![],
+            sampled: None,
+            parse_stats: ParseStats::default(),
+        }
+    }
+
+    /// take over the parse options of `options`, keeping path and data
+    pub fn apply_options(&mut self, options: &CSVFile) {
+        let filepath = std::mem::take(&mut self.filepath);
+        let data = std::mem::take(&mut self.data);
+        let rows = std::mem::take(&mut self.rows);
+        *self = CSVFile {
+            filepath,
+            data,
+            rows,
+            sampled: self.sampled,
+            parse_stats: self.parse_stats,
+            ..options.options()
+        };
+    }
+}
+
+/// `rows` is kept in step with `data`, unless it is empty
+fn handle_non_finite(
+    data: &mut Vec<[f64; 2]>,
+    rows: &mut Vec<usize>,
+    policy: NonFinitePolicy,
+    warnings: &mut Vec<ParseWarning>,
+) {
+    let count = data.iter().filter(|[_, y]| !y.is_finite()).count();
+    if count == 0 {
+        return;
+    }
+    warnings.push(ParseWarning::NonFinite { count, policy });
+    match policy {
+        NonFinitePolicy::Keep => (),
+        NonFinitePolicy::Drop => {
+            if !rows.is_empty() {
+                let mut points = data.iter();
+                rows.retain(|_| points.next().is_some_and(|[_, y]| y.is_finite()));
+            }
+            data.retain(|[_, y]| y.is_finite())
+        }
+        NonFinitePolicy::Interpolate => {
+            let finite: Vec<[f64; 2]> = data
+                .iter()
+                .copied()
+                .filter(|[_, y]| y.is_finite())
+                .collect();
+            let mut num_finite_before: usize = 0;
+            let mut kept = Vec::with_capacity(data.len());
+            data.retain_mut(|point| {
+                if point[1].is_finite() {
+                    num_finite_before += 1;
+                    kept.push(true);
+                    return true;
+                }
+                // points without finite neighbors on both sides are dropped
+                let (Some(prev), Some(next)) = (
+                    num_finite_before.checked_sub(1).map(|i| finite[i]),
+                    finite.get(num_finite_before),
+                ) else {
+                    kept.push(false);
+                    return false;
+                };
+                kept.push(true);
+                point[1] = if next[0] == prev[0] {
+                    prev[1]
+                } else {
+                    prev[1] + (next[1] - prev[1]) * (point[0] - prev[0]) / (next[0] - prev[0])
+                };
+                true
+            });
+            if !rows.is_empty() {
+                let mut kept = kept.into_iter();
+                rows.retain(|_| kept.next().unwrap_or(false));
+            }
+        }
+    }
+}
+
+/// the stride input of `size` bytes is sampled with, plotting millions of
+/// points would make the UI unresponsive, `None` if all rows are read
+fn sample_stride(size: usize, options: &CSVFile) -> Option<usize> {
+    (!options.load_full && size > SAMPLE_THRESHOLD_BYTES)
+        .then(|| size.div_ceil(SAMPLE_THRESHOLD_BYTES))
+}
+
+/// stream the lines of `reader` and keep those that `parse_bytes` would hand
+/// to the parser, but of the records only every `stride`-th, so that the
+/// memory needed does not grow with the file, records with line breaks in
+/// quoted fields are not supported here, the lines of the file the kept
+/// lines come from are only listed if `options.keep_rows` is set
+fn sample_lines(
+    mut reader: impl BufRead,
+    options: &CSVFile,
+    stride: usize,
+) -> std::io::Result<(Vec<u8>, ParseStats, LineMap)> {
+    let mut sampler = LineSampler {
+        options,
+        stride,
+        // the field pattern reads all lines, the csv reader takes the first
+        // record as header, which is always kept
+        records: (!options.field_pattern.is_empty()).then_some(0),
+        sampled: Vec::new(),
+        stats: ParseStats::default(),
+        lines: LineMap::default(),
+    };
+    // lines that may still turn out to be part of the footer, blank lines at
+    // the end do not count, like in `drop_footer`
+    let mut pending: VecDeque<(usize, Vec<u8>)> = VecDeque::new();
+    let mut num_lines = 0;
+    loop {
+        let mut line = Vec::new();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        num_lines += 1;
+        if num_lines == 1 {
+            line = strip_bom(&line).to_vec();
+        }
+        if num_lines <= options.skip_header {
+            continue;
+        }
+        let blank = line.trim_ascii().is_empty();
+        pending.push_back((num_lines, line));
+        while !blank && pending.len() > options.skip_footer {
+            if let Some((num, line)) = pending.pop_front() {
+                sampler.push(num, &line);
+            }
+        }
+    }
+    Ok((sampler.sampled, sampler.stats, sampler.lines))
+}
+
+// state of `sample_lines`
+struct LineSampler<'a> {
+    options: &'a CSVFile,
+    stride: usize,
+    // records after the header so far, `None` before the header
+    records: Option<usize>,
+    sampled: Vec<u8>,
+    stats: ParseStats,
+    lines: LineMap,
+}
+
+impl LineSampler<'_> {
+    /// count line `num` like `count_lines` and keep it if it is the header
+    /// or a sampled record, after the changes `parse_bytes` makes to it
+    fn push(&mut self, num: usize, line: &[u8]) {
+        let options = self.options;
+        let comment_prefix = options.comment_prefix.as_bytes();
+        self.stats.total += 1;
+        let content = line.trim_ascii_start();
+        if content.first() == Some(&options.comment_char)
+            || (!comment_prefix.is_empty() && content.starts_with(comment_prefix))
+        {
+            self.stats.comments += 1;
+        }
+        let stripped;
+        let line = if options.strip_inline_comments {
+            stripped = strip_inline_comments(line, options.comment_char);
+            stripped.as_slice()
+        } else {
+            line
+        };
+        let content = line.trim_ascii_start();
+        if (options.skip_blank_lines && content.is_empty())
+            || (!comment_prefix.is_empty() && content.starts_with(comment_prefix))
+        {
+            return;
+        }
+        // lines the parser skips are left out as well
+        let is_record = if options.field_pattern.is_empty() {
+            !matches!(line, b"\n" | b"\r\n") && line.first() != Some(&options.comment_char)
+        } else {
+            content.first() != Some(&options.comment_char)
+        };
+        if !is_record {
+            return;
+        }
+        let keep = match self.records {
+            None => true,
+            Some(n) => n % self.stride == 0,
+        };
+        self.records = Some(self.records.map_or(0, |n| n + 1));
+        if keep {
+            self.sampled.extend_from_slice(line);
+            if !line.ends_with(b"\n") {
+                self.sampled.push(b'\n');
+            }
+            if options.keep_rows {
+                self.lines.lines.push(num);
+            }
+        }
+    }
+}
+
+/// a UTF-8 byte order mark would otherwise end up in the first field
+fn strip_bom(raw: &[u8]) -> &[u8] {
+    raw.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(raw)
+}
+
+/// `raw` without its first `num_lines` physical lines, e.g. a preamble
+/// before the column names
+fn drop_header(raw: &[u8], num_lines: usize) -> &[u8] {
+    let mut start = 0;
+    for _ in 0..num_lines {
+        match raw[start..].iter().position(|b| *b == b'\n') {
+            Some(pos) => start += pos + 1,
+            None => return &[],
+        }
+    }
+    &raw[start..]
+}
+
+/// `raw` without its last `num_lines` physical lines, blank lines at the end
+/// are removed first, so that a trailing line break does not matter
+fn drop_footer(raw: &[u8], num_lines: usize) -> &[u8] {
+    if num_lines == 0 {
+        return raw;
+    }
+    let mut end = raw.trim_ascii_end().len();
+    for _ in 0..num_lines {
+        end = raw[..end].iter().rposition(|b| *b == b'\n').unwrap_or(0);
+    }
+    &raw[..end]
+}
+
+/// `ParseStats` with the number of lines of `raw` and of those that are
+/// comments, the parsed and failed rows are counted while parsing
+fn count_lines(raw: &[u8], comment_char: u8, comment_prefix: &[u8]) -> ParseStats {
+    let raw = raw.trim_ascii_end();
+    if raw.is_empty() {
+        return ParseStats::default();
+    }
+    let mut stats = ParseStats::default();
+    for line in raw.split(|b| *b == b'\n') {
+        stats.total += 1;
+        let content = line.trim_ascii_start();
+        if content.first() == Some(&comment_char)
+            || (!comment_prefix.is_empty() && content.starts_with(comment_prefix))
+        {
+            stats.comments += 1;
+        }
+    }
+    stats
+}
+
+/// remove lines starting with `comment_prefix` (if it is not empty) and,
+/// with `skip_blank`, lines that only hold whitespace, the 1-based numbers of
+/// the kept lines are added to `kept`
+fn filter_lines(
+    raw: &[u8],
+    comment_prefix: &[u8],
+    skip_blank: bool,
+    mut kept: Option<&mut Vec<usize>>,
+) -> Vec<u8> {
+    let mut filtered = Vec::with_capacity(raw.len());
+    for (i, line) in raw.split_inclusive(|b| *b == b'\n').enumerate() {
+        let content = line.trim_ascii_start();
+        if skip_blank && content.is_empty() {
+            continue;
+        }
+        if !comment_prefix.is_empty() && content.starts_with(comment_prefix) {
+            continue;
+        }
+        filtered.extend_from_slice(line);
+        if let Some(kept) = kept.as_mut() {
+            kept.push(i + 1);
+        }
+    }
+    filtered
+}
+
+/// where the lines handed to the parser are in the file, which differs once
+/// header lines, filtered lines or the lines left out by sampling are gone
+#[derive(Default)]
+struct LineMap {
+    // lines dropped from the start of the file
+    offset: usize,
+    // 1-based line after `offset` of every parsed line, empty if no line was
+    // dropped in between
+    lines: Vec<usize>,
+}
+
+impl LineMap {
+    /// 1-based line of the file of the 1-based `line` of the parsed bytes
+    fn file_line(&self, line: usize) -> usize {
+        self.offset + self.lines.get(line - 1).copied().unwrap_or(line)
+    }
+}
+
+/// finds the lines of the records the csv reader returns, the reader's own
+/// line count leaves out comments and its positions point at the blank and
+/// comment lines it skipped before a record
+struct RecordLines<'a> {
+    bytes: &'a [u8],
+    comment_char: u8,
+    map: &'a LineMap,
+    // byte at the start of 1-based `line` of `bytes`
+    byte: usize,
+    line: usize,
+}
+
+impl<'a> RecordLines<'a> {
+    fn new(bytes: &'a [u8], comment_char: u8, map: &'a LineMap) -> Self {
+        Self {
+            bytes,
+            comment_char,
+            map,
+            byte: 0,
+            line: 1,
+        }
+    }
+
+    /// 1-based line of the file of the record read from `byte` on, the
+    /// records have to be passed in order
+    fn file_line(&mut self, byte: usize) -> usize {
+        let byte = byte.clamp(self.byte, self.bytes.len());
+        self.line += self.bytes[self.byte..byte]
+            .iter()
+            .filter(|b| **b == b'\n')
+            .count();
+        self.byte = byte;
+        while let Some(end) = self.bytes[self.byte..].iter().position(|b| *b == b'\n') {
+            let line = &self.bytes[self.byte..self.byte + end];
+            if !(line.is_empty() || line == b"\r" || line.first() == Some(&self.comment_char)) {
+                break;
+            }
+            self.byte += end + 1;
+            self.line += 1;
+        }
+        self.map.file_line(self.line)
+    }
+}
+
+// cut every line at the first occurrence of the comment character, along
+// with the whitespace in front of it
+fn strip_inline_comments(raw: &[u8], comment_char: u8) -> Vec<u8> {
+    let mut stripped = Vec::with_capacity(raw.len());
+    for line in raw.split_inclusive(|b| *b == b'\n') {
+        match line.iter().position(|b| *b == comment_char) {
+            Some(pos) => {
+                stripped.extend_from_slice(line[..pos].trim_ascii_end());
+                stripped.push(b'\n');
+            }
+            None => stripped.extend_from_slice(line),
+        }
+    }
+    stripped
+}
+
+// number of records the column detection looks at
+const DETECT_ROWS: usize = 1000;
+
+/// the first column whose values all parse and strictly increase, and the
+/// next numeric column after it
+fn detect_columns<R: std::io::Read>(
+    mut rdr: csv::Reader<R>,
+    number_format: NumberFormat,
+) -> Option<(usize, usize)> {
+    // per column: all values numeric so far, and the last value
+    let mut columns: Vec<(bool, bool, Option<f64>)> = vec![];
+    for record in rdr.records().take(DETECT_ROWS) {
+        let Ok(record) = record else {
+            continue;
+        };
+        if columns.is_empty() {
+            columns = vec![(true, true, None); record.len()];
+        }
+        for (col, (numeric, increasing, last)) in columns.iter_mut().enumerate() {
+            match record
+                .get(col)
+                .and_then(|field| number_format.parse(field).ok())
+            {
+                Some(value) => {
+                    if last.is_some_and(|last| value <= last) {
+                        *increasing = false;
+                    }
+                    *last = Some(value);
+                }
+                None => *numeric = false,
+            }
+        }
+    }
+    let xcol = columns
+        .iter()
+        .position(|(numeric, increasing, last)| *numeric && *increasing && last.is_some())?;
+    let ycol = columns
+        .iter()
+        .skip(xcol + 1)
+        .position(|(numeric, _, _)| *numeric)?;
+    Some((xcol, xcol + 1 + ycol))
+}
+
+/// the first `num_rows` records of `preview` split into fields with the
+/// delimiter and comment character of `options`, `None` if a record can not
+/// be read or there is none
+pub fn preview_fields(
+    preview: &str,
+    options: &CSVFile,
+    num_rows: usize,
+) -> Option<Vec<Vec<String>>> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .comment(Some(options.comment_char))
+        .delimiter(options.delimiter)
+        .has_headers(false)
+        // header lines often have fewer fields than the data
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(preview.as_bytes());
+    let rows = rdr
+        .records()
+        .take(num_rows)
+        .map(|record| Some(record.ok()?.iter().map(str::to_owned).collect()))
+        .collect::<Option<Vec<Vec<String>>>>()?;
+    (!rows.is_empty()).then_some(rows)
+}
+
+/// the compiled field pattern, or why it can not be used
+pub fn compile_field_pattern(pattern: &str, x_is_index: bool) -> Result<regex::Regex, String> {
+    let regex = regex::Regex::new(pattern).map_err(|err| err.to_string())?;
+    let has_group = |name: &str| regex.capture_names().flatten().any(|group| group == name);
+    if !has_group("y") || (!x_is_index && !has_group("x")) {
+        return Err("the pattern needs the named groups (?<x>...) and (?<y>...)".to_string());
     }
+    Ok(regex)
 }
 
-fn parse_rows(
-    mut rdr: csv::Reader<std::fs::File>,
-    xcol: usize,
-    ycol: usize,
-    filepath: &Path,
-    error_log: &mut Vec<String>,
+/// the lines of `raw` that match `options.field_pattern` as points, lines
+/// starting with the comment character are skipped, their lines in the file
+/// are added to `rows` if `options.keep_rows` is set, `raw` holds every
+/// `sample_stride`-th line of the file
+fn parse_lines(
+    raw: &[u8],
+    options: &CSVFile,
+    sample_stride: usize,
+    lines: &LineMap,
+    rows: &mut Vec<usize>,
+    warnings: &mut Vec<ParseWarning>,
+    stats: &mut ParseStats,
 ) -> Vec<[f64; 2]> {
+    let regex = match compile_field_pattern(&options.field_pattern, options.x_is_index) {
+        Ok(regex) => regex,
+        Err(err) => {
+            warnings.push(ParseWarning::InvalidPattern(err));
+            return vec![];
+        }
+    };
+    let text = String::from_utf8_lossy(raw);
     let mut data = Vec::<[f64; 2]>::new();
-    for (i, entry) in rdr.records().enumerate() {
-        if let Err(e) = entry {
-            error_log.push(format!(
-                "WARNING: could not parse row {} of file {filepath:?}: {}",
-                i + 1,
-                e
-            ));
+    let mut num_unmatched = 0;
+    // the lines are numbered before the comments are skipped
+    let records = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim_start().starts_with(options.comment_char as char));
+    // a stride of 0 would skip everything, treat it like 1
+    for (i, (line_index, line)) in records.enumerate().step_by(options.stride.max(1)) {
+        let i = i * sample_stride;
+        let row = i + 1;
+        let Some(captures) = regex.captures(line) else {
+            num_unmatched += 1;
             continue;
-        }
-        let entry = entry.unwrap();
-        let x = entry.iter().nth(xcol).map(|x| x.parse::<f64>());
-        let y = entry.iter().nth(ycol).map(|y| y.parse::<f64>());
-        match (x, y) {
+        };
+        let value = |axis: char| {
+            let field = captures.name(&axis.to_string())?.as_str().trim();
+            Some(
+                options
+                    .number_format
+                    .parse(field)
+                    .map_err(|err| ParseWarning::InvalidValue {
+                        row,
+                        axis,
+                        col: 0,
+                        message: format!("{:?}: {}", field, err),
+                    }),
+            )
+        };
+        let x = if options.x_is_index {
+            Some(Ok(i as f64))
+        } else {
+            value('x')
+        };
+        match (x, value('y')) {
             (Some(Ok(x)), Some(Ok(y))) => {
                 data.push([x, y]);
+                if options.keep_rows {
+                    rows.push(lines.file_line(line_index + 1));
+                }
             }
-            (Some(Ok(_)), Some(Err(e))) => {
-                error_log.push(format!(
-                    "WARNING: y-column {ycol} could not be parsed in entry {} for file {filepath:?}: {}",
-                    i + 1,
-                    e
-                ));
-                continue;
+            (Some(Err(warning)), _) | (_, Some(Err(warning))) => {
+                stats.failed += 1;
+                warnings.push(warning)
             }
-            (Some(Err(e)), Some(Ok(_))) => {
-                error_log.push(format!(
-                    "WARNING: x-column {xcol} could not be parsed in entry {} for file {filepath:?}: {}",
-                    i + 1,
-                    e
-                ));
+            // optional groups that did not take part in the match
+            _ => num_unmatched += 1,
+        }
+    }
+    stats.failed += num_unmatched;
+    if num_unmatched > 0 {
+        warnings.push(ParseWarning::UnmatchedLines {
+            count: num_unmatched,
+        });
+    }
+    data
+}
+
+/// the records of `rdr` as points, reading only every `options.stride`-th,
+/// their lines in the file are added to `rows` if `options.keep_rows` is
+/// set, `rdr` holds every `sample_stride`-th record of the file
+fn parse_rows<R: std::io::Read>(
+    mut rdr: csv::Reader<R>,
+    options: &CSVFile,
+    sample_stride: usize,
+    mut lines: RecordLines,
+    rows: &mut Vec<usize>,
+    warnings: &mut Vec<ParseWarning>,
+    stats: &mut ParseStats,
+) -> Vec<[f64; 2]> {
+    let (xcol, ycol) = if options.parametric {
+        (options.param_xcol, options.param_ycol)
+    } else {
+        (options.xcol, options.ycol)
+    };
+    let x_is_index = options.x_is_index && !options.parametric;
+    let mut data = Vec::<[f64; 2]>::new();
+    // a stride of 0 would skip everything, treat it like 1
+    for (i, entry) in rdr.records().enumerate().step_by(options.stride.max(1)) {
+        let i = i * sample_stride;
+        let row = i + 1;
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                stats.failed += 1;
+                warnings.push(ParseWarning::InvalidRecord {
+                    row,
+                    message: e.to_string(),
+                });
                 continue;
             }
-            _ => {
-                error_log.push(format!(
-                    "WARNING: could not parse columns {xcol}, {ycol} in entry {} for file {filepath:?}",
-                    i + 1
-                ));
-                continue;
+        };
+        let x = if x_is_index {
+            Some(Ok(i as f64))
+        } else {
+            entry
+                .iter()
+                .nth(xcol)
+                .map(|x| options.number_format.parse(x))
+        };
+        let y = entry
+            .iter()
+            .nth(ycol)
+            .map(|y| options.number_format.parse(y));
+        if !matches!((&x, &y), (Some(Ok(_)), Some(Ok(_)))) {
+            stats.failed += 1;
+        }
+        match (x, y) {
+            (Some(Ok(x)), Some(Ok(y))) => {
+                data.push([x, y]);
+                if let (true, Some(position)) = (options.keep_rows, entry.position()) {
+                    rows.push(lines.file_line(position.byte() as usize));
+                }
             }
+            (Some(Ok(_)), Some(Err(e))) => warnings.push(ParseWarning::InvalidValue {
+                row,
+                axis: 'y',
+                col: ycol,
+                message: e.to_string(),
+            }),
+            (Some(Err(e)), Some(Ok(_))) => warnings.push(ParseWarning::InvalidValue {
+                row,
+                axis: 'x',
+                col: xcol,
+                message: e.to_string(),
+            }),
+            _ => warnings.push(ParseWarning::MissingColumns { row, xcol, ycol }),
         }
     }
     data
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// parse `raw` as the content of a file with `options`
+    fn parse(raw: &[u8], options: &CSVFile) -> (Option<CSVFile>, Vec<String>) {
+        // tests run in parallel, each on its own thread
+        let thread = format!("{:?}", std::thread::current().id());
+        let thread: String = thread.chars().filter(char::is_ascii_digit).collect();
+        let path = std::env::temp_dir().join(format!("plotme_test_{thread}.csv"));
+        std::fs::write(&path, raw).unwrap();
+        let mut errors = vec![];
+        let csvfile = CSVFile::new(path.clone(), options, &mut errors);
+        std::fs::remove_file(path).unwrap();
+        (csvfile, errors)
+    }
+
+    #[test]
+    fn inline_comments_are_stripped() {
+        assert_eq!(
+            strip_inline_comments(b"1,2 # first\n3,4\n# only a comment\n5,6#", b'#'),
+            b"1,2\n3,4\n\n5,6\n"
+        );
+        let options = CSVFile {
+            strip_inline_comments: true,
+            ..Default::default()
+        };
+        let raw = b"x,y\n1,2 # calibrated\n3,4\t# drift\n5,6\n";
+        let (csvfile, warnings) = parse(raw, &options);
+        assert!(warnings.is_empty());
+        assert_eq!(
+            csvfile.unwrap().data,
+            vec![[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]]
+        );
+        // without the option the last column holds the comment
+        let (_, warnings) = parse(raw, &CSVFile::default());
+        assert_eq!(warnings.len(), 2);
+    }
+
+    #[test]
+    fn stride_keeps_every_nth_record() {
+        let raw: String = (0..10).map(|i| format!("{i},{}\n", i * i)).collect();
+        let xs = |stride| {
+            let options = CSVFile {
+                stride,
+                ..Default::default()
+            };
+            let (csvfile, _) = parse(raw.as_bytes(), &options);
+            csvfile
+                .unwrap()
+                .data
+                .iter()
+                .map(|[x, _]| *x)
+                .collect::<Vec<_>>()
+        };
+        // the first line is the header, the stride starts at the first record
+        assert_eq!(xs(1), vec![1., 2., 3., 4., 5., 6., 7., 8., 9.]);
+        assert_eq!(xs(3), vec![1., 4., 7.]);
+        assert_eq!(xs(4), vec![1., 5., 9.]);
+        assert_eq!(xs(20), vec![1.]);
+        assert_eq!(xs(0), xs(1));
+    }
+
+    #[test]
+    fn rows_are_lines_of_the_file() {
+        let raw = "preamble\nx,y\n# comment\n1,2\n\n3,4\n5,6\n";
+        let rows = |options: CSVFile| {
+            let options = CSVFile {
+                skip_header: 1,
+                keep_rows: true,
+                ..options
+            };
+            let (csvfile, _) = parse(raw.as_bytes(), &options);
+            csvfile.unwrap().rows
+        };
+        assert_eq!(rows(CSVFile::default()), vec![4, 6, 7]);
+        assert_eq!(
+            rows(CSVFile {
+                comment_prefix: "#".into(),
+                skip_blank_lines: true,
+                ..Default::default()
+            }),
+            vec![4, 6, 7]
+        );
+        assert_eq!(
+            rows(CSVFile {
+                field_pattern: r"(?<x>\d+),(?<y>\d+)".into(),
+                ..Default::default()
+            }),
+            vec![4, 6, 7]
+        );
+        assert_eq!(
+            rows(CSVFile {
+                stride: 2,
+                ..Default::default()
+            }),
+            vec![4, 7]
+        );
+    }
+
+    #[test]
+    fn bom_and_crlf_load_without_warnings() {
+        assert_eq!(strip_bom(b"\xEF\xBB\xBFx,y"), b"x,y");
+        assert_eq!(strip_bom(b"x,y"), b"x,y");
+        let raw = b"\xEF\xBB\xBFx,y\r\n1.5 , 2 \r\n3,4\t\r\n5,6\r\n";
+        let (csvfile, warnings) = parse(raw, &CSVFile::default());
+        assert!(warnings.is_empty());
+        assert_eq!(
+            csvfile.unwrap().data,
+            vec![[1.5, 2.0], [3.0, 4.0], [5.0, 6.0]]
+        );
+    }
+
+    #[test]
+    fn comment_prefix_and_blank_lines_are_filtered() {
+        let raw = b"% header\n1 2\n  % indented\n\n3 4\n   \n5 6";
+        assert_eq!(
+            filter_lines(raw, b"%", false, None),
+            b"1 2\n\n3 4\n   \n5 6"
+        );
+        assert_eq!(
+            filter_lines(raw, b"", true, None),
+            b"% header\n1 2\n  % indented\n3 4\n5 6"
+        );
+        assert_eq!(filter_lines(raw, b"%", true, None), b"1 2\n3 4\n5 6");
+    }
+
+    #[test]
+    fn percent_comments_and_data_blocks_are_parsed() {
+        let options = CSVFile {
+            comment_prefix: "%".into(),
+            skip_blank_lines: true,
+            ..Default::default()
+        };
+        let raw = b"x,y\n% block 1\n1,2\n3,4\n\n% block 2\n5,6\n\n";
+        let (csvfile, warnings) = parse(raw, &options);
+        assert!(warnings.is_empty());
+        assert_eq!(
+            csvfile.unwrap().data,
+            vec![[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]]
+        );
+    }
+
+    #[test]
+    fn footer_lines_are_dropped() {
+        // a trailing line break does not count as a line
+        assert_eq!(drop_footer(b"1,2\n3,4\nend\n", 1), b"1,2\n3,4");
+        assert_eq!(drop_footer(b"1,2\n3,4\nend", 1), b"1,2\n3,4");
+        assert_eq!(drop_footer(b"1,2\n3,4\nend\n\n \n", 1), b"1,2\n3,4");
+        assert_eq!(drop_footer(b"1,2\n3,4\nend\n", 0), b"1,2\n3,4\nend\n");
+        // the `\r` left at the end is trimmed by the csv reader
+        assert_eq!(drop_footer(b"1,2\r\n3,4\r\nend\r\n", 1), b"1,2\r\n3,4\r");
+        assert_eq!(drop_footer(b"1,2\n3,4\n", 2), b"");
+        assert_eq!(drop_footer(b"1,2\n3,4\n", 5), b"");
+    }
+
+    #[test]
+    fn skip_footer_counts_lines_not_records() {
+        let options = CSVFile {
+            skip_footer: 1,
+            ..Default::default()
+        };
+        for raw in [
+            &b"x,y\n1,2\n3,4\ntotal\n"[..],
+            b"x,y\n1,2\n3,4\ntotal",
+            b"x,y\r\n1,2\r\n3,4\r\ntotal\r\n",
+        ] {
+            let (csvfile, warnings) = parse(raw, &options);
+            assert!(warnings.is_empty());
+            assert_eq!(csvfile.unwrap().data, vec![[1.0, 2.0], [3.0, 4.0]]);
+        }
+    }
+
+    #[test]
+    fn streamed_sample_matches_a_strided_parse() {
+        let mut raw = b"\xEF\xBB\xBFinstrument xyz\nx,y\n# comment\n".to_vec();
+        for i in 0..20 {
+            raw.extend(format!("{i},{}\n", i * i).bytes());
+            if i % 7 == 0 {
+                raw.extend(b"\n# block\n");
+            }
+        }
+        raw.extend(b"total\n\n");
+        let options = CSVFile {
+            skip_header: 1,
+            skip_footer: 1,
+            keep_rows: true,
+            ..Default::default()
+        };
+        for options in [
+            options.clone(),
+            CSVFile {
+                x_is_index: true,
+                ..options.clone()
+            },
+            CSVFile {
+                skip_blank_lines: true,
+                ..options.clone()
+            },
+            CSVFile {
+                field_pattern: r"(?<x>\d+),(?<y>\d+)".into(),
+                ..options.clone()
+            },
+        ] {
+            let (sampled, stats, lines) = sample_lines(&raw[..], &options, 3).unwrap();
+            let (csvfile, _) = CSVFile::parse_prepared(
+                "test.csv".into(),
+                &sampled,
+                stats,
+                lines,
+                Some(3),
+                &options,
+            );
+            let csvfile = csvfile.unwrap();
+            let strided = CSVFile {
+                stride: 3,
+                ..options.clone()
+            };
+            let (expected, _) = parse(&raw, &strided);
+            let expected = expected.unwrap();
+            assert_eq!(csvfile.data, expected.data);
+            assert_eq!(csvfile.rows, expected.rows);
+            assert_eq!(csvfile.parse_stats.total, expected.parse_stats.total);
+            assert_eq!(csvfile.parse_stats.comments, expected.parse_stats.comments);
+            assert_eq!(csvfile.sampled, Some(3));
+        }
+    }
+
+    #[test]
+    fn thousands_point_and_decimal_comma() {
+        let format = NumberFormat::CommaWithThousandsPoint;
+        assert_eq!(format.parse("1.234,56"), Ok(1234.56));
+        assert_eq!(format.parse("-0,5"), Ok(-0.5));
+        let options = CSVFile {
+            delimiter: b';',
+            number_format: format,
+            ..Default::default()
+        };
+        let raw = b"x;y\n1;1.234,56\n2;2.000.000,5\n";
+        let (csvfile, warnings) = parse(raw, &options);
+        assert!(warnings.is_empty());
+        assert_eq!(
+            csvfile.unwrap().data,
+            vec![[1.0, 1234.56], [2.0, 2000000.5]]
+        );
+    }
+
+    #[test]
+    fn header_lines_are_dropped() {
+        assert_eq!(drop_header(b"a\nb\nc", 0), b"a\nb\nc");
+        assert_eq!(drop_header(b"a\nb\nc", 2), b"c");
+        assert_eq!(drop_header(b"a\r\nb\r\n", 1), b"b\r\n");
+        assert_eq!(drop_header(b"a\nb", 5), b"");
+    }
+
+    #[test]
+    fn skip_header_is_applied_when_parsing() {
+        let options = CSVFile {
+            skip_header: 2,
+            ..Default::default()
+        };
+        let raw = b"instrument xyz\nsettings 1\nx,y\n1,2\n3,4\n";
+        let (csvfile, _) = parse(raw, &options);
+        assert_eq!(csvfile.unwrap().data, vec![[1.0, 2.0], [3.0, 4.0]]);
+    }
+}
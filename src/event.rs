@@ -1,4 +1,10 @@
-use crate::App;
+use std::path::PathBuf;
+
+use crate::{
+    app::ManipulationDefaults,
+    file_entry::{FileEntryState, Manipulations},
+    App,
+};
 
 pub trait AppEvent {
     fn apply(&mut self, app: &mut App) -> Vec<String>;
@@ -6,29 +12,216 @@ pub trait AppEvent {
         let errors = self.apply(app);
         app.errors.extend(errors);
     }
+    /// events that can be undone are kept on the undo stack after running
+    fn undoable(&self) -> bool {
+        false
+    }
+    /// revert the changes made by `apply`
+    fn undo(&mut self, _app: &mut App) -> Vec<String> {
+        Vec::new()
+    }
 }
 
-struct SetActive {
+/// make a plotted file active, so that drag manipulations apply to it, or
+/// deactivate it again
+pub struct SetActive {
     file_id: usize,
+    active: bool,
+    // deactivate all other files when activating
+    exclusive: bool,
+    // false for quick switches like cycling with Tab, which would flood the undo stack
+    undoable: bool,
+    // states of the changed files and the primary file before the event ran
+    previous_states: Vec<(usize, FileEntryState)>,
+    previous_primary: Option<usize>,
 }
 
 impl SetActive {
-    fn new(file_id: usize) -> Self {
-        Self { file_id }
+    pub fn new(file_id: usize, active: bool) -> Self {
+        Self {
+            file_id,
+            active,
+            exclusive: false,
+            undoable: true,
+            previous_states: Vec::new(),
+            previous_primary: None,
+        }
+    }
+    /// make the file the only active one
+    pub fn exclusive(file_id: usize) -> Self {
+        Self {
+            exclusive: true,
+            ..Self::new(file_id, true)
+        }
+    }
+    /// like `exclusive`, but not kept on the undo stack
+    pub fn cycled(file_id: usize) -> Self {
+        Self {
+            undoable: false,
+            ..Self::exclusive(file_id)
+        }
     }
 }
 
 impl AppEvent for SetActive {
     fn apply(&mut self, app: &mut App) -> Vec<String> {
-        for folder in app.folders.iter_mut() {
-            for file_entry in folder.files.iter_mut() {
-                if file_entry.id == self.file_id {
+        let Some(file_entry) = app
+            .folders
+            .iter()
+            .flat_map(|folder| &folder.files)
+            .find(|file_entry| file_entry.id == self.file_id)
+        else {
+            let err_msg = format!("ERROR: file with id {} not found", self.file_id);
+            return vec![err_msg];
+        };
+        if !file_entry.is_plotted() {
+            return vec![format!(
+                "WARNING: only plotted files can be active, {} is not plotted.",
+                file_entry.filename
+            )];
+        }
+        self.previous_primary = app.primary_file;
+        let states = &mut self.previous_states;
+        for file_entry in app.folders.iter_mut().flat_map(|folder| &mut folder.files) {
+            if file_entry.id == self.file_id {
+                states.push((file_entry.id, file_entry.state()));
+                if self.active {
                     file_entry.set_active();
-                    return Vec::new();
+                } else {
+                    file_entry.deactivate();
                 }
+            } else if self.exclusive && file_entry.is_active() {
+                states.push((file_entry.id, file_entry.state()));
+                file_entry.deactivate();
+            }
+        }
+        if self.active {
+            app.primary_file = Some(self.file_id);
+        }
+        Vec::new()
+    }
+    fn undoable(&self) -> bool {
+        self.undoable && !self.previous_states.is_empty()
+    }
+    fn undo(&mut self, app: &mut App) -> Vec<String> {
+        if self.previous_states.is_empty() {
+            return Vec::new();
+        }
+        app.primary_file = self.previous_primary;
+        for file_entry in app.folders.iter_mut().flat_map(|folder| &mut folder.files) {
+            if let Some((_, state)) = self
+                .previous_states
+                .iter()
+                .find(|(id, _)| *id == file_entry.id)
+            {
+                file_entry.restore_state(*state);
+            }
+        }
+        Vec::new()
+    }
+}
+
+/// reset the manipulations of every file in every folder, see `Manipulations`
+pub struct ResetManipulations {
+    defaults: Manipulations,
+    previous: Vec<(usize, Manipulations)>,
+}
+
+impl ResetManipulations {
+    /// scale and offsets are reset to `defaults`
+    pub fn new(defaults: &ManipulationDefaults) -> Self {
+        Self {
+            defaults: Manipulations::new(defaults),
+            previous: Vec::new(),
+        }
+    }
+}
+
+impl AppEvent for ResetManipulations {
+    fn apply(&mut self, app: &mut App) -> Vec<String> {
+        self.previous.clear();
+        for file_entry in app.folders.iter_mut().flat_map(|folder| &mut folder.files) {
+            self.previous
+                .push((file_entry.id, file_entry.manipulations()));
+            file_entry.set_manipulations(self.defaults.clone());
+        }
+        vec![format!(
+            "INFO: reset the manipulations of {} files.",
+            self.previous.len()
+        )]
+    }
+    fn undoable(&self) -> bool {
+        !self.previous.is_empty()
+    }
+    fn undo(&mut self, app: &mut App) -> Vec<String> {
+        for file_entry in app.folders.iter_mut().flat_map(|folder| &mut folder.files) {
+            if let Some((_, manipulations)) =
+                self.previous.iter().find(|(id, _)| *id == file_entry.id)
+            {
+                file_entry.set_manipulations(manipulations.clone());
+            }
+        }
+        Vec::new()
+    }
+}
+
+/// plot or hide all files of a folder that match the search phrase
+pub struct SetFolderPlotted {
+    folder_path: PathBuf,
+    search_phrase: String,
+    plotted: bool,
+    previous_states: Vec<(usize, FileEntryState)>,
+}
+
+impl SetFolderPlotted {
+    pub fn new(folder_path: PathBuf, search_phrase: &str, plotted: bool) -> Self {
+        Self {
+            folder_path,
+            search_phrase: search_phrase.to_owned(),
+            plotted,
+            previous_states: Vec::new(),
+        }
+    }
+}
+
+impl AppEvent for SetFolderPlotted {
+    fn apply(&mut self, app: &mut App) -> Vec<String> {
+        let Some(folder) = app
+            .folders
+            .iter_mut()
+            .find(|folder| folder.path == self.folder_path)
+        else {
+            let err_msg = format!("ERROR: folder {:?} not found", self.folder_path);
+            return vec![err_msg];
+        };
+        self.previous_states.clear();
+        for file_entry in folder.files.iter_mut() {
+            if !file_entry.matches_search_phrase(&self.search_phrase) {
+                continue;
+            }
+            self.previous_states
+                .push((file_entry.id, file_entry.state()));
+            if self.plotted {
+                file_entry.plot(&folder.path, &folder.default_options, &mut app.loader);
+            } else {
+                file_entry.hide();
+            }
+        }
+        Vec::new()
+    }
+    fn undoable(&self) -> bool {
+        true
+    }
+    fn undo(&mut self, app: &mut App) -> Vec<String> {
+        for file_entry in app.folders.iter_mut().flat_map(|folder| &mut folder.files) {
+            if let Some((_, state)) = self
+                .previous_states
+                .iter()
+                .find(|(id, _)| *id == file_entry.id)
+            {
+                file_entry.restore_state(*state);
             }
         }
-        let err_msg = format!("ERROR: file with id {} not found", self.file_id);
-        return vec![err_msg];
+        Vec::new()
     }
 }
@@ -0,0 +1,923 @@
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use egui::Color32;
+use plotters::{
+    coord::{
+        ranged1d::{KeyPointHint, NoDefaultFormatting, ValueFormatter},
+        types::RangedCoordf32,
+        Shift,
+    },
+    prelude::*,
+    style::text_anchor::{HPos, Pos, VPos},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    csvfile::CSVFile,
+    errors::ErrorStringExt,
+    file_entry::{FileEntry, RenderMode},
+    fit::{linear_fit, stats},
+    pdf::PDFBackend,
+    plot::{parse_ticks, spaced_ticks, tick_label, PlotDimensions, Tick, TickFormat},
+    transform::{even_grid, resample_linear, sorted_finite},
+    App,
+};
+
+/// appearance of exported figures, the defaults match the original export
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ExportStyle {
+    pub background: Color32,
+    /// color of the title, the tick labels and the legend labels
+    #[serde(default = "default_text_color")]
+    pub text_color: Color32,
+    /// opacity of the legend background, which uses the background color
+    pub legend_alpha: f64,
+    /// factor on the widths of exported lines, independent of the screen
+    #[serde(default = "default_line_width_factor")]
+    pub line_width_factor: f64,
+    #[serde(default)]
+    pub page_size: PageSize,
+    /// grid color of sessions saved before it moved to `GridOptions::color`,
+    /// it is taken over on load
+    #[serde(default, skip_serializing)]
+    pub(crate) mesh: Option<Color32>,
+}
+
+/// paper of exported PDFs, in landscape orientation like the plot
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PageSize {
+    #[default]
+    A4,
+    A5,
+    Letter,
+    /// the page has the size of the chart, without a white border
+    FitToPlot,
+}
+
+impl PageSize {
+    pub const ALL: [PageSize; 4] = [Self::A4, Self::A5, Self::Letter, Self::FitToPlot];
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::A4 => "A4",
+            Self::A5 => "A5",
+            Self::Letter => "Letter",
+            Self::FitToPlot => "Fit to plot",
+        }
+    }
+    /// width and height in points
+    fn size(&self) -> (f64, f64) {
+        match self {
+            Self::A4 => (841.89, 595.28),
+            Self::A5 => (595.28, 419.53),
+            Self::Letter => (792.0, 612.0),
+            Self::FitToPlot => (EXPORT_SIZE.0 as f64, EXPORT_SIZE.1 as f64),
+        }
+    }
+}
+
+fn default_line_width_factor() -> f64 {
+    1.0
+}
+
+fn default_text_color() -> Color32 {
+    Color32::BLACK
+}
+
+impl ExportStyle {
+    /// plotters only knows integer stroke widths, lines stay at least 1px wide
+    fn stroke_width(&self, base: f64) -> u32 {
+        (base * self.line_width_factor).round().max(1.0) as u32
+    }
+}
+
+impl Default for ExportStyle {
+    fn default() -> Self {
+        Self {
+            background: Color32::WHITE,
+            text_color: default_text_color(),
+            legend_alpha: 0.8,
+            line_width_factor: default_line_width_factor(),
+            page_size: PageSize::default(),
+            mesh: None,
+        }
+    }
+}
+
+impl App {
+    pub fn save_svg(&self) -> Result<(), String> {
+        let filepath = if let Some(path) = rfd::FileDialog::new().save_file() {
+            path
+        } else {
+            return Err("ERROR: selected path unvalid.".to_string());
+        };
+        let file_entries: Vec<&FileEntry> = self.exported_file_entries().collect();
+        write_svg(&filepath, self, &file_entries)
+    }
+
+    /// the plot as a vector PDF, with the same layout as the SVG export
+    pub fn save_pdf(&self) -> Result<(), String> {
+        let Some(filepath) = rfd::FileDialog::new()
+            .add_filter("PDF", &["pdf"])
+            .save_file()
+        else {
+            return Err("ERROR: selected path unvalid.".to_string());
+        };
+        let file_entries: Vec<&FileEntry> = self.exported_file_entries().collect();
+        write_pdf(&filepath, self, &file_entries)
+    }
+
+    /// plot `data_file` alone without a file dialog, e.g. for data piped in
+    /// on the command line, `filepath` ending in `.pdf` gives a PDF, any
+    /// other an SVG
+    pub fn export_data(
+        &self,
+        name: &str,
+        data_file: CSVFile,
+        filepath: &Path,
+    ) -> Result<(), String> {
+        let mut file_entry = FileEntry::with_data(name.to_string(), data_file, 0);
+        file_entry.color = self.color_cycle.color(0);
+        file_entry.tab = Some(self.plot_tabs.active().id);
+        let is_pdf = filepath
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"));
+        if is_pdf {
+            write_pdf(filepath, self, &[&file_entry])
+        } else {
+            write_svg(filepath, self, &[&file_entry])
+        }
+    }
+
+    /// the SVG document of the plot, for pasting into vector graphics programs
+    pub fn svg_string(&self) -> Result<String, String> {
+        let file_entries: Vec<&FileEntry> = self.exported_file_entries().collect();
+        let mut svg = String::new();
+        {
+            let root = SVGBackend::with_string(&mut svg, EXPORT_SIZE).into_drawing_area();
+            draw_chart(&root, self, &file_entries)?;
+            root.present()
+                .err_to_string("ERROR: unable to render SVG output")?;
+        }
+        Ok(svg)
+    }
+
+    /// only the legend of the plot, with one entry per line, as a PNG if the
+    /// file name ends in `.png` and as an SVG otherwise
+    pub fn save_legend(&self) -> Result<(), String> {
+        let entries = self.legend_entries();
+        if entries.is_empty() {
+            return Err("WARNING: no plotted series for the legend.".to_string());
+        }
+        let Some(filepath) = rfd::FileDialog::new()
+            .add_filter("SVG", &["svg"])
+            .add_filter("PNG", &["png"])
+            .save_file()
+        else {
+            return Err("ERROR: selected path unvalid.".to_string());
+        };
+        let longest = entries
+            .iter()
+            .map(|(label, _)| label.chars().count())
+            .max()
+            .unwrap_or(0);
+        // there is no text measurement without a font, so the width is an
+        // estimate based on the number of characters
+        let width = 2 * LEGEND_MARGIN + LEGEND_SWATCH + 10 + (longest as f64 * 9.0) as u32;
+        let height = 2 * LEGEND_MARGIN + LEGEND_ROW * entries.len() as u32;
+        let is_png = filepath
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+        if is_png {
+            let root = BitMapBackend::new(&filepath, (width, height)).into_drawing_area();
+            draw_legend(&root, self, &entries)?;
+            root.present()
+                .err_to_string("ERROR: unable to write PNG output")
+        } else {
+            let root = SVGBackend::new(&filepath, (width, height)).into_drawing_area();
+            draw_legend(&root, self, &entries)?;
+            root.present()
+                .err_to_string("ERROR: unable to write SVG output")
+        }
+    }
+
+    /// labels and colors of the series in the legend of the exported plot
+    fn legend_entries(&self) -> Vec<(String, Color32)> {
+        let tab = self.plot_tabs.active().id;
+        self.exported_file_entries()
+            .map(|file_entry| (file_entry.display_name(), file_entry.color))
+            .chain(
+                self.averages
+                    .iter()
+                    .filter(|curve| curve.tab == tab)
+                    .map(|curve| (curve.name.clone(), curve.color)),
+            )
+            .collect()
+    }
+
+    /// write every plotted series into its own SVG file
+    pub fn batch_export_svg(&mut self) {
+        let Some(directory) = rfd::FileDialog::new().pick_folder() else {
+            self.errors
+                .push("WARNING: No directory given for batch export.".to_string());
+            return;
+        };
+        let mut messages = Vec::new();
+        let mut used_names = Vec::new();
+        let mut num_written = 0;
+        for file_entry in self.exported_file_entries() {
+            let filepath = unique_svg_path(&directory, &file_entry.filename, &mut used_names);
+            match write_svg(&filepath, self, &[file_entry]) {
+                Ok(()) => num_written += 1,
+                Err(msg) => messages.push(format!("{} ({:?})", msg, filepath)),
+            }
+        }
+        let num_failed = messages.len();
+        self.errors.extend(messages);
+        self.errors.push(format!(
+            "INFO: batch export wrote {} SVG files, {} failed.",
+            num_written, num_failed
+        ));
+    }
+
+    pub fn export_style_ui(&mut self, ui: &mut egui::Ui) {
+        let style = &mut self.export_style;
+        ui.horizontal(|ui| {
+            ui.label("Background");
+            egui::color_picker::color_edit_button_srgba(
+                ui,
+                &mut style.background,
+                egui::color_picker::Alpha::Opaque,
+            );
+            ui.label("Text");
+            egui::color_picker::color_edit_button_srgba(
+                ui,
+                &mut style.text_color,
+                egui::color_picker::Alpha::Opaque,
+            );
+        });
+        ui.add(egui::Slider::new(&mut style.legend_alpha, 0.0..=1.0).text("Legend opacity"));
+        ui.add(
+            egui::Slider::new(&mut style.line_width_factor, 0.5..=5.0)
+                .text("Line width factor")
+                .step_by(0.5),
+        )
+        .on_hover_text("Widen the lines of the export without changing the plot on screen");
+        ui.horizontal(|ui| {
+            ui.label("PDF page");
+            egui::ComboBox::from_id_source("pdf_page_size")
+                .selected_text(style.page_size.label())
+                .show_ui(ui, |ui| {
+                    for page_size in PageSize::ALL {
+                        ui.selectable_value(&mut style.page_size, page_size, page_size.label());
+                    }
+                });
+        });
+        if ui.button("Reset Style").clicked() {
+            *style = ExportStyle::default();
+        }
+    }
+
+    /// tab separated table of the transformed data of the exported files
+    /// within the current x-bounds, interpolated onto a shared x-grid
+    pub fn visible_data_table(&mut self) -> Option<String> {
+        let dims = &self.plot_tabs.active().dims;
+        let (x0, x1) = (dims.x0 as f64, dims.x1 as f64);
+        let columns: Vec<(String, Vec<[f64; 2]>)> = self
+            .exported_file_entries()
+            .map(|file_entry| {
+                let data = sorted_finite(&file_entry.transformed_data());
+                let visible: Vec<[f64; 2]> = data
+                    .into_iter()
+                    .filter(|[x, _]| (x0..=x1).contains(x))
+                    .collect();
+                (file_entry.display_name(), visible)
+            })
+            .filter(|(_, data)| !data.is_empty())
+            .collect();
+        if columns.is_empty() {
+            self.errors
+                .push("WARNING: no plotted data within the current x-range.".to_string());
+            return None;
+        }
+        let first_x = |data: &Vec<[f64; 2]>| data[0][0];
+        let last_x = |data: &Vec<[f64; 2]>| data[data.len() - 1][0];
+        let start = columns
+            .iter()
+            .map(|(_, data)| first_x(data))
+            .fold(f64::INFINITY, f64::min);
+        let end = columns
+            .iter()
+            .map(|(_, data)| last_x(data))
+            .fold(f64::NEG_INFINITY, f64::max);
+        let overlap_start = columns
+            .iter()
+            .map(|(_, data)| first_x(data))
+            .fold(f64::NEG_INFINITY, f64::max);
+        let overlap_end = columns
+            .iter()
+            .map(|(_, data)| last_x(data))
+            .fold(f64::INFINITY, f64::min);
+        if overlap_start > overlap_end {
+            self.errors.push(
+                "WARNING: the x-ranges of the copied files do not overlap, \
+                 cells outside of a file's x-range are left empty."
+                    .to_string(),
+            );
+        }
+        let num_points = columns
+            .iter()
+            .map(|(_, data)| data.len())
+            .max()
+            .unwrap_or(1)
+            .min(MAX_TABLE_ROWS);
+        let mut table = std::iter::once("x".to_string())
+            .chain(columns.iter().map(|(name, _)| name.clone()))
+            .collect::<Vec<_>>()
+            .join("\t");
+        let grid = even_grid(start, end, num_points);
+        let resampled: Vec<Vec<f64>> = columns
+            .iter()
+            .map(|(_, data)| resample_linear(data, &grid))
+            .collect();
+        for (i, x) in grid.iter().enumerate() {
+            table.push('\n');
+            table.push_str(&x.to_string());
+            for ys in &resampled {
+                table.push('\t');
+                if !ys[i].is_nan() {
+                    table.push_str(&ys[i].to_string());
+                }
+            }
+        }
+        Some(table)
+    }
+
+    /// the files drawn in the active plot tab
+    fn exported_file_entries(&self) -> impl Iterator<Item = &FileEntry> {
+        let tab = self.plot_tabs.active().id;
+        // entries without color have not been drawn on screen yet
+        self.folders
+            .iter()
+            .flat_map(|folder| &folder.files)
+            .filter(move |file_entry| {
+                file_entry.is_drawn_in(tab) && file_entry.color != Color32::TRANSPARENT
+            })
+    }
+}
+
+// size of exported plots in px, PDFs scale it to their page
+const EXPORT_SIZE: (u32, u32) = (1024, 768);
+
+// layout of an exported legend in px
+const LEGEND_MARGIN: u32 = 10;
+const LEGEND_ROW: u32 = 25;
+const LEGEND_SWATCH: u32 = 20;
+
+// upper limit of the rows of a copied data table
+const MAX_TABLE_ROWS: usize = 100_000;
+
+fn write_svg(filepath: &Path, app: &App, file_entries: &[&FileEntry]) -> Result<(), String> {
+    let root = SVGBackend::new(filepath, EXPORT_SIZE).into_drawing_area();
+    draw_chart(&root, app, file_entries)?;
+    root.present()
+        .err_to_string("ERROR: unable to write SVG output")
+}
+
+fn write_pdf(filepath: &Path, app: &App, file_entries: &[&FileEntry]) -> Result<(), String> {
+    let root = PDFBackend::new(filepath, EXPORT_SIZE, app.export_style.page_size.size())
+        .into_drawing_area();
+    draw_chart(&root, app, file_entries)?;
+    root.present()
+        .err_to_string("ERROR: unable to write PDF output")
+}
+
+/// draw the legend `entries` from top to bottom on any plotters backend
+fn draw_legend<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    app: &App,
+    entries: &[(String, Color32)],
+) -> Result<(), String> {
+    let style = &app.export_style;
+    root.fill(&export_color(style.background))
+        .err_to_string("ERROR: to prepare canvas for export")?;
+    let text_color = export_color(style.text_color);
+    let text_style = TextStyle::from((app.plot_options.title_font_family.as_str(), 16.0))
+        .color(&text_color)
+        .pos(Pos::new(HPos::Left, VPos::Center));
+    for (i, (label, color)) in entries.iter().enumerate() {
+        let x = LEGEND_MARGIN as i32;
+        let y = (LEGEND_MARGIN + LEGEND_ROW * i as u32 + LEGEND_ROW / 2) as i32;
+        let swatch = LEGEND_SWATCH as i32;
+        root.draw(&Rectangle::new(
+            [(x, y - 5), (x + swatch, y + 5)],
+            export_color(*color).filled(),
+        ))
+        .err_to_string("ERROR: unable to draw legend for export")?;
+        root.draw(&Text::new(
+            label.as_str(),
+            (x + swatch + 10, y),
+            &text_style,
+        ))
+        .err_to_string("ERROR: unable to draw legend for export")?;
+    }
+    Ok(())
+}
+
+/// draw the chart with `file_entries` on any plotters backend, using the
+/// plot settings of `app`
+fn draw_chart<DB: DrawingBackend>(
+    root: &DrawingArea<DB, Shift>,
+    app: &App,
+    file_entries: &[&FileEntry],
+) -> Result<(), String> {
+    let (xrange, yrange) = chart_ranges(&app.plot_tabs.active().dims, file_entries);
+    let options = &app.plot_options;
+    let style = &app.export_style;
+
+    root.fill(&export_color(style.background))
+        .err_to_string("ERROR: to prepare canvas for export")?;
+
+    let mut builder = ChartBuilder::on(root);
+    builder
+        .margin(20u32)
+        .x_label_area_size(30u32)
+        .y_label_area_size(30u32);
+    // the caption area is reserved above the chart
+    if !options.title.is_empty() {
+        builder.caption(
+            &options.title,
+            (options.title_font_family.as_str(), options.title_font_size)
+                .with_color(export_color(style.text_color)),
+        );
+    }
+    let mut chart = builder
+        .build_cartesian_2d(
+            TickAxis::new(
+                reversed_if(xrange.clone(), options.x_reversed),
+                &options.x_ticks,
+                options.grid.x_spacing,
+                options.x_format,
+            ),
+            TickAxis::new(
+                reversed_if(yrange, options.y_reversed),
+                &options.y_ticks,
+                options.grid.y_spacing,
+                options.y_format,
+            ),
+        )
+        .err_to_string("ERROR: unable to build chart for export")?;
+
+    let grid = options.grid;
+    let mut mesh = chart.configure_mesh();
+    mesh.x_labels(3)
+        .y_labels(3)
+        // the default label font of plotters, in the text color
+        .label_style(
+            ("sans-serif", 12.percent().max(12)).with_color(export_color(style.text_color)),
+        )
+        .bold_line_style(export_color(grid.color))
+        .light_line_style(export_color(grid.color).mix(0.5));
+    if !grid.show {
+        mesh.disable_mesh();
+    }
+    mesh.draw()
+        .err_to_string("ERROR: unable to prepare labels for export")?;
+
+    let (x0, x1) = (xrange.start as f64, xrange.end as f64);
+    for file_entry in file_entries {
+        let color = export_color(file_entry.color).stroke_width(style.stroke_width(2.0));
+
+        if file_entry.show_original {
+            let faint = export_color(file_entry.color)
+                .mix(0.3)
+                .stroke_width(style.stroke_width(1.0));
+            for part in clip_to_x_range(&file_entry.data_file.data, x0, x1) {
+                chart
+                    .draw_series(LineSeries::new(
+                        part.into_iter()
+                            .filter(|[x, y]| x.is_finite() && y.is_finite())
+                            .map(|[x, y]| (x as f32, y as f32)),
+                        faint,
+                    ))
+                    .err_to_string("ERROR: unable to draw original data for export")?;
+            }
+        }
+
+        if file_entry.fill {
+            let fill = export_color(file_entry.color)
+                .mix(file_entry.fill_alpha as f64)
+                .filled();
+            for segment in file_entry.line_segments() {
+                for part in clip_to_x_range(&segment, x0, x1) {
+                    chart
+                        .draw_series(AreaSeries::new(
+                            part.into_iter()
+                                .filter(|[x, y]| x.is_finite() && y.is_finite())
+                                .map(|[x, y]| (x as f32, y as f32)),
+                            file_entry.fill_baseline as f32,
+                            fill,
+                        ))
+                        .err_to_string("ERROR: unable to draw filled area for export")?;
+                }
+            }
+        }
+
+        let segments = file_entry
+            .line_segments()
+            .iter()
+            .flat_map(|segment| clip_to_x_range(segment, x0, x1))
+            .collect::<Vec<_>>();
+        // files outside of the x-range still get their legend entry
+        let segments = if segments.is_empty() {
+            vec![vec![]]
+        } else {
+            segments
+        };
+        for (i, segment) in segments.into_iter().enumerate() {
+            let series = chart
+                .draw_series(LineSeries::new(
+                    segment
+                        .into_iter()
+                        .filter(|[x, y]| x.is_finite() && y.is_finite())
+                        .map(|[x, y]| (x as f32, y as f32)),
+                    color,
+                ))
+                .err_to_string("ERROR: unable to draw data for export")?;
+            // only one legend entry per file
+            if i == 0 {
+                series
+                    .label(file_entry.display_name())
+                    .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+            }
+        }
+
+        if file_entry.render_mode == RenderMode::LinePoints {
+            chart
+                .draw_series(
+                    file_entry
+                        .transformed_data()
+                        .into_iter()
+                        .filter(|[x, y]| (x0..=x1).contains(x) && y.is_finite())
+                        .map(|[x, y]| {
+                            Circle::new(
+                                (x as f32, y as f32),
+                                file_entry.marker_size,
+                                color.filled(),
+                            )
+                        }),
+                )
+                .err_to_string("ERROR: unable to draw markers for export")?;
+        }
+
+        if file_entry.fit_linear {
+            // an export is drawn once, it does not need the cached fit
+            if let Some(fit) = linear_fit(&file_entry.transformed_data()) {
+                let fit_line = fit.line();
+                chart
+                    .draw_series(DashedLineSeries::new(
+                        fit_line.map(|[x, y]| (x as f32, y as f32)),
+                        10,
+                        5,
+                        color,
+                    ))
+                    .err_to_string("ERROR: unable to draw linear fit for export")?;
+            }
+        }
+    }
+
+    let tab = app.plot_tabs.active().id;
+    for curve in app.averages.iter().filter(|curve| curve.tab == tab) {
+        let color = export_color(curve.color).stroke_width(style.stroke_width(2.0));
+        if curve.show_band {
+            let upper = curve
+                .points
+                .iter()
+                .map(|[x, y, std]| (*x as f32, (y + std) as f32));
+            let lower = curve
+                .points
+                .iter()
+                .rev()
+                .map(|[x, y, std]| (*x as f32, (y - std) as f32));
+            chart
+                .draw_series(std::iter::once(Polygon::new(
+                    upper.chain(lower).collect::<Vec<_>>(),
+                    export_color(curve.color).mix(0.2).filled(),
+                )))
+                .err_to_string("ERROR: unable to draw standard deviation for export")?;
+        }
+        chart
+            .draw_series(LineSeries::new(
+                curve.mean().into_iter().map(|[x, y]| (x as f32, y as f32)),
+                color,
+            ))
+            .err_to_string("ERROR: unable to draw average for export")?
+            .label(&curve.name)
+            .legend(move |(x, y)| PathElement::new(vec![(x, y), (x + 20, y)], color));
+    }
+
+    for annotation in app.annotations.iter().filter(|a| a.tab == tab) {
+        let color = export_color(annotation.color);
+        let text_style = TextStyle::from(
+            (
+                options.title_font_family.as_str(),
+                annotation.font_size as f64,
+            )
+                .into_font(),
+        )
+        .color(&color)
+        // egui_plot centers texts on their position
+        .pos(Pos::new(HPos::Center, VPos::Center));
+        chart
+            .draw_series(std::iter::once(Text::new(
+                annotation.text.clone(),
+                (annotation.x as f32, annotation.y as f32),
+                text_style,
+            )))
+            .err_to_string("ERROR: unable to draw annotation for export")?;
+    }
+
+    chart
+        .configure_series_labels()
+        .background_style(export_color(style.background).mix(style.legend_alpha))
+        .border_style(BLACK)
+        .label_font(("sans-serif", 12).with_color(export_color(style.text_color)))
+        .position(SeriesLabelPosition::UpperRight)
+        .draw()
+        .err_to_string("ERROR: unable to configure labels for export")?;
+
+    Ok(())
+}
+
+/// f32 axis with grid lines and labels at custom ticks, or every `spacing`
+/// of the grid, or at the positions plotters chooses for a plain f32 range
+struct TickAxis {
+    coord: RangedCoordf32,
+    ticks: Vec<Tick>,
+    spacing: f64,
+    format: TickFormat,
+}
+
+impl TickAxis {
+    fn new(range: Range<f32>, tick_spec: &str, spacing: f64, format: TickFormat) -> Self {
+        Self {
+            coord: range.into(),
+            ticks: parse_ticks(tick_spec),
+            spacing,
+            format,
+        }
+    }
+}
+
+impl Ranged for TickAxis {
+    type ValueType = f32;
+    type FormatOption = NoDefaultFormatting;
+
+    fn map(&self, value: &f32, limit: (i32, i32)) -> i32 {
+        self.coord.map(value, limit)
+    }
+
+    fn key_points<Hint: KeyPointHint>(&self, hint: Hint) -> Vec<f32> {
+        // the range of a reversed axis starts at its larger end
+        let range = self.coord.range();
+        let (low, high) = (range.start.min(range.end), range.start.max(range.end));
+        let spaced = spaced_ticks(self.spacing, low as f64, high as f64);
+        if self.ticks.is_empty() && spaced.is_empty() {
+            self.coord.key_points(hint)
+        } else if hint.weight().allow_light_points() {
+            // no light grid lines between custom ticks
+            vec![]
+        } else if self.ticks.is_empty() {
+            spaced.into_iter().map(|value| value as f32).collect()
+        } else {
+            self.ticks
+                .iter()
+                .map(|tick| tick.value as f32)
+                .filter(|value| (low..=high).contains(value))
+                .collect()
+        }
+    }
+
+    fn range(&self) -> Range<f32> {
+        self.coord.range()
+    }
+}
+
+impl ValueFormatter<f32> for TickAxis {
+    fn format_ext(&self, value: &f32) -> String {
+        tick_label(&self.ticks, *value as f64)
+            .or_else(|| self.format.format(*value as f64))
+            .unwrap_or_else(|| RangedCoordf32::format(value))
+    }
+}
+
+/// the parts of `points` inside `[x0, x1]`, each extended by the neighboring
+/// points just outside, so that the lines reach the border of the chart, a
+/// segment that spans the whole range is kept as well
+fn clip_to_x_range(points: &[[f64; 2]], x0: f64, x1: f64) -> Vec<Vec<[f64; 2]>> {
+    if let [point] = points {
+        let inside = (x0..=x1).contains(&point[0]);
+        return if inside { vec![vec![*point]] } else { vec![] };
+    }
+    let mut parts: Vec<Vec<[f64; 2]>> = vec![];
+    let mut continued = false;
+    for pair in points.windows(2) {
+        let [[xa, _], [xb, _]] = [pair[0], pair[1]];
+        if xa.min(xb) <= x1 && xa.max(xb) >= x0 {
+            match parts.last_mut() {
+                Some(part) if continued => part.push(pair[1]),
+                _ => parts.push(pair.to_vec()),
+            }
+            continued = true;
+        } else {
+            continued = false;
+        }
+    }
+    parts
+}
+
+/// `<directory>/<file stem>.svg`, with a numbered suffix if the name was already used
+fn unique_svg_path(directory: &Path, filename: &str, used_names: &mut Vec<String>) -> PathBuf {
+    let stem = Path::new(filename)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| filename.to_owned());
+    let mut name = format!("{}.svg", stem);
+    let mut n = 2;
+    while used_names.contains(&name) {
+        name = format!("{}_{}.svg", stem, n);
+        n += 1;
+    }
+    used_names.push(name.clone());
+    directory.join(name)
+}
+
+/// the axis ranges of the exported chart, the plot bounds are only known
+/// once it has been shown, before that (or if they collapsed to a single
+/// value) the bounds of the data are used instead
+fn chart_ranges(
+    plot_dims: &PlotDimensions,
+    file_entries: &[&FileEntry],
+) -> (Range<f32>, Range<f32>) {
+    let is_valid = |range: &Range<f32>| {
+        range.start.is_finite() && range.end.is_finite() && range.start < range.end
+    };
+    let mut xrange = plot_dims.x0..plot_dims.x1;
+    let mut yrange = plot_dims.y0..plot_dims.y1;
+    if !is_valid(&xrange) || !is_valid(&yrange) {
+        let data_stats = file_entries
+            .iter()
+            .filter_map(|file_entry| stats(&file_entry.transformed_data()));
+        let mut data_x = f32::INFINITY..f32::NEG_INFINITY;
+        let mut data_y = f32::INFINITY..f32::NEG_INFINITY;
+        for data_stats in data_stats {
+            data_x.start = data_x.start.min(data_stats.xmin as f32);
+            data_x.end = data_x.end.max(data_stats.xmax as f32);
+            data_y.start = data_y.start.min(data_stats.ymin as f32);
+            data_y.end = data_y.end.max(data_stats.ymax as f32);
+        }
+        if !is_valid(&xrange) {
+            xrange = data_x;
+        }
+        if !is_valid(&yrange) {
+            yrange = data_y;
+        }
+    }
+    (widen_degenerate(xrange), widen_degenerate(yrange))
+}
+
+/// a range around the value of a range that has zero width (e.g. a single
+/// data point), and the unit range if there is no usable value at all
+pub(crate) fn widen_degenerate(range: Range<f32>) -> Range<f32> {
+    if range.start.is_finite() && range.end.is_finite() && range.start < range.end {
+        return range;
+    }
+    let center = if range.start.is_finite() {
+        range.start
+    } else {
+        0.0
+    };
+    let half_width = if center == 0.0 {
+        0.5
+    } else {
+        center.abs() * 0.05
+    };
+    (center - half_width)..(center + half_width)
+}
+
+/// plotters draws ranges from their start to their end, so a reversed range
+/// reverses the axis
+fn reversed_if(range: Range<f32>, reversed: bool) -> Range<f32> {
+    if reversed {
+        range.end..range.start
+    } else {
+        range
+    }
+}
+
+/// `Color32` stores premultiplied alpha, while SVG expects the plain sRGB
+/// channels next to a separate opacity
+fn export_color(color: Color32) -> RGBAColor {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    RGBAColor(r, g, b, a as f64 / 255.)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{app::ManipulationDefaults, file_entry::get_file_entries};
+
+    #[test]
+    fn exported_colors_are_unmultiplied_srgb() {
+        let RGBAColor(r, g, b, a) = export_color(Color32::from_rgb(31, 119, 180));
+        assert_eq!((r, g, b, a), (31, 119, 180, 1.0));
+        // stored premultiplied, the round trip may be off by one
+        let RGBAColor(r, g, b, a) =
+            export_color(Color32::from_rgba_unmultiplied(31, 119, 180, 128));
+        assert!(r.abs_diff(31) <= 1 && g.abs_diff(119) <= 1 && b.abs_diff(180) <= 1);
+        assert_eq!(a, 128. / 255.);
+    }
+
+    #[test]
+    fn clipping_keeps_segments_that_cross_the_range() {
+        let points = [[0.0, 0.0], [1.0, 1.0], [2.0, 2.0], [3.0, 3.0], [10.0, 10.0]];
+        assert_eq!(
+            clip_to_x_range(&points, 1.5, 2.5),
+            vec![vec![[1.0, 1.0], [2.0, 2.0], [3.0, 3.0]]]
+        );
+        // no point lies inside the range, but the last segment crosses it
+        assert_eq!(
+            clip_to_x_range(&points, 5.0, 6.0),
+            vec![vec![[3.0, 3.0], [10.0, 10.0]]]
+        );
+        // the curve leaves the range and comes back
+        let points = [[0.0, 0.0], [5.0, 1.0], [6.0, 2.0], [1.0, 3.0]];
+        assert_eq!(
+            clip_to_x_range(&points, 0.0, 2.0),
+            vec![vec![[0.0, 0.0], [5.0, 1.0]], vec![[6.0, 2.0], [1.0, 3.0]]]
+        );
+        assert_eq!(
+            clip_to_x_range(&[[1.0, 1.0]], 0.0, 2.0),
+            vec![vec![[1.0, 1.0]]]
+        );
+        assert!(clip_to_x_range(&[[3.0, 1.0]], 0.0, 2.0).is_empty());
+    }
+
+    fn file_entry(name: &str, data: Vec<[f64; 2]>) -> FileEntry {
+        let dir = std::env::temp_dir().join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("test.csv"), "").unwrap();
+        let mut file_entry =
+            get_file_entries(&dir, &mut 0, &ManipulationDefaults::default(), &mut vec![])
+                .pop()
+                .unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+        file_entry.data_file.data = data;
+        file_entry.color = Color32::RED;
+        file_entry
+    }
+
+    #[test]
+    fn export_before_the_plot_was_shown() {
+        // the plot dimensions are all zero until the plot was drawn once
+        let app = App::default();
+        let file_entry = file_entry("plotme_export_unshown", vec![[1.0, 2.0], [3.0, 5.0]]);
+        let (xrange, yrange) = chart_ranges(&app.plot_tabs.active().dims, &[&file_entry]);
+        assert_eq!(xrange, 1.0..3.0);
+        assert_eq!(yrange, 2.0..5.0);
+        let mut svg = String::new();
+        {
+            let root = SVGBackend::with_string(&mut svg, (1024, 768)).into_drawing_area();
+            draw_chart(&root, &app, &[&file_entry]).unwrap();
+            root.present().unwrap();
+        }
+        assert!(svg.contains("<polyline"));
+    }
+
+    #[test]
+    fn single_point_is_widened() {
+        let app = App::default();
+        let file_entry = file_entry("plotme_export_single_point", vec![[2.0, 0.0]]);
+        let (xrange, yrange) = chart_ranges(&app.plot_tabs.active().dims, &[&file_entry]);
+        assert_eq!(xrange, 1.9..2.1);
+        assert_eq!(yrange, -0.5..0.5);
+    }
+
+    #[test]
+    fn legend_uses_the_text_color() {
+        let mut app = App::default();
+        app.export_style.background = Color32::BLACK;
+        app.export_style.text_color = Color32::from_rgb(0xEE, 0xDD, 0xCC);
+        let entries = [("test.csv".to_string(), Color32::RED)];
+        let mut svg = String::new();
+        {
+            let root = SVGBackend::with_string(&mut svg, (200, 50)).into_drawing_area();
+            draw_legend(&root, &app, &entries).unwrap();
+            root.present().unwrap();
+        }
+        assert!(svg.contains("test.csv"));
+        assert!(svg.contains("#EEDDCC"));
+    }
+}
@@ -1,10 +1,15 @@
 use std::iter::Iterator;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use egui::Color32;
 use serde::{Deserialize, Serialize};
 
-use crate::{app::FloatInput, csvfile::CSVFile};
+use crate::{
+    app::{FloatInput, ManipulationDefaults},
+    csvfile::CSVFile,
+    fit::{linear_fit, stats, LinearFit, Stats},
+    loader::Loader,
+};
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct FileEntry {
@@ -17,61 +22,485 @@ pub struct FileEntry {
     state: FileEntryState,
     pub id: usize,
     pub preview: String,
+    #[serde(default)]
+    pub fit_linear: bool,
+    /// plot the running sum of the y-values, before scale and offset
+    #[serde(default)]
+    pub cumulative_sum: bool,
+    /// shift y so that the curve starts at zero, after the cumulative sum
+    /// and before scale and offset
+    #[serde(default)]
+    pub zero_at_start: bool,
+    /// map the x-range of the data onto 0..1, before the x-offset
+    #[serde(default)]
+    pub normalize_x: bool,
+    /// plot `20·log10(|y| / decibel_ref)`, after the cumulative sum and
+    /// before zero at start, scale and offset
+    #[serde(default)]
+    pub decibel: bool,
+    #[serde(default = "default_decibel_ref")]
+    pub decibel_ref: f64,
+    #[serde(default)]
+    pub offset_mode: OffsetMode,
+    /// draw the untransformed data faintly behind the transformed curve
+    #[serde(default)]
+    pub show_original: bool,
+    /// the line is interrupted where consecutive x-values are further apart
+    #[serde(default)]
+    pub gap_threshold: Option<f64>,
+    /// points with x outside of these bounds are dropped before all transforms
+    #[serde(default)]
+    pub x_min: Option<f64>,
+    #[serde(default)]
+    pub x_max: Option<f64>,
+    /// lower and upper bound of the plotted y-values, applied after all
+    /// transforms, e.g. to keep a spike from compressing the plot
+    #[serde(default)]
+    pub y_clamp: Option<(f64, f64)>,
+    #[serde(default)]
+    pub clamp_mode: ClampMode,
+    #[serde(default)]
+    pub render_mode: RenderMode,
+    #[serde(default)]
+    pub step_mode: StepMode,
+    /// marker radius in points
+    #[serde(default = "default_marker_size")]
+    pub marker_size: f32,
+    /// shade the area between the curve and `fill_baseline`
+    #[serde(default)]
+    pub fill: bool,
+    #[serde(default = "default_fill_alpha")]
+    pub fill_alpha: f32,
+    #[serde(default)]
+    pub fill_baseline: f64,
+    /// hidden files keep their state and settings, but are not drawn
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+    /// files with the same group are listed together in the tree
+    #[serde(default)]
+    pub group: Option<String>,
+    /// id of the plot tab the file is drawn in, plotted files without a
+    /// tab are assigned to the active one
+    #[serde(default)]
+    pub tab: Option<usize>,
+    // whether the folder's default parse options were taken over
+    #[serde(default)]
+    options_seeded: bool,
+    /// the pointer is close to the file's line in the plot
+    #[serde(skip)]
+    pub hovered_in_plot: bool,
+    /// folder shown in front of the filename, see `qualify_file_labels`
+    #[serde(skip)]
+    pub folder_label: Option<String>,
+    // the last load read the file, but no data points were left
+    #[serde(skip)]
+    parsed_empty: bool,
+    // generation of the running load, results of other loads are dropped
+    #[serde(skip)]
+    load_generation: usize,
+    // statistics of the transformed data and the transform they were computed for
+    #[serde(skip)]
+    stats_cache: Option<(String, Option<Stats>)>,
+    // fill polygons and the transform, baseline, gaps and steps they were built for
+    #[serde(skip)]
+    fill_cache: Option<(String, Vec<Vec<[f64; 2]>>)>,
+    // linear fit of the transformed data and the transform it was computed for
+    #[serde(skip)]
+    fit_cache: Option<(String, Option<LinearFit>)>,
+    // `indexed_data` and the transform it was computed for
+    #[serde(skip)]
+    index_cache: Option<(String, Vec<[f64; 2]>, Vec<usize>)>,
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, Eq)]
-enum FileEntryState {
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum FileEntryState {
     Idle,
     Plotted,
     PreviouslyPlotted,
     Active,
     NeedsConfig,
+    Loading,
 }
 
 impl FileEntryState {}
 
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RenderMode {
+    #[default]
+    Line,
+    /// line with a marker at every sample
+    LinePoints,
+}
+
+impl RenderMode {
+    pub const ALL: [RenderMode; 2] = [Self::Line, Self::LinePoints];
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Line => "Line",
+            Self::LinePoints => "Line + Points",
+        }
+    }
+}
+
+/// scale, offsets, transforms, x-range, y-clamp, linear fit, original curve,
+/// gaps and steps of a file, the part of its settings that is reset to start
+/// over, color and markers stay
+#[derive(Clone)]
+pub struct Manipulations {
+    scale: FloatInput,
+    offset: FloatInput,
+    xoffset: FloatInput,
+    cumulative_sum: bool,
+    zero_at_start: bool,
+    normalize_x: bool,
+    decibel: bool,
+    offset_mode: OffsetMode,
+    fit_linear: bool,
+    show_original: bool,
+    gap_threshold: Option<f64>,
+    step_mode: StepMode,
+    y_clamp: Option<(f64, f64)>,
+    clamp_mode: ClampMode,
+    x_min: Option<f64>,
+    x_max: Option<f64>,
+}
+
+impl Manipulations {
+    /// the manipulations of a new file, its scale and offsets start at
+    /// `defaults`
+    pub fn new(defaults: &ManipulationDefaults) -> Self {
+        let ManipulationDefaults {
+            scale,
+            offset,
+            xoffset,
+        } = defaults.clone();
+        Self {
+            scale,
+            offset,
+            xoffset,
+            cumulative_sum: false,
+            zero_at_start: false,
+            normalize_x: false,
+            decibel: false,
+            offset_mode: OffsetMode::default(),
+            fit_linear: false,
+            show_original: false,
+            gap_threshold: None,
+            step_mode: StepMode::default(),
+            y_clamp: None,
+            clamp_mode: ClampMode::default(),
+            x_min: None,
+            x_max: None,
+        }
+    }
+}
+
+/// unit of the y-offset of a file
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OffsetMode {
+    /// y-units of the plot
+    #[default]
+    Absolute,
+    /// multiples of the y-range of the scaled data, so that curves of
+    /// different magnitudes stack evenly
+    FractionOfRange,
+}
+
+impl OffsetMode {
+    pub const ALL: [OffsetMode; 2] = [Self::Absolute, Self::FractionOfRange];
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Absolute => "Absolute",
+            Self::FractionOfRange => "× y-range",
+        }
+    }
+}
+
+/// what happens to points outside of `FileEntry::y_clamp`
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ClampMode {
+    /// move them onto the nearest bound
+    #[default]
+    Clip,
+    /// leave them out
+    Drop,
+}
+
+impl ClampMode {
+    pub const ALL: [ClampMode; 2] = [Self::Clip, Self::Drop];
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Clip => "Clip",
+            Self::Drop => "Drop",
+        }
+    }
+}
+
+/// how the line connects consecutive points
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum StepMode {
+    /// straight lines
+    #[default]
+    None,
+    /// the y-value of a point already holds from the previous point on
+    StepBefore,
+    /// the y-value of a point holds until the next point
+    StepAfter,
+}
+
+impl StepMode {
+    pub const ALL: [StepMode; 3] = [Self::None, Self::StepBefore, Self::StepAfter];
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::None => "Linear",
+            Self::StepBefore => "Step before",
+            Self::StepAfter => "Step after",
+        }
+    }
+}
+
+/// difference of the largest and smallest finite y-value, 0 without any
+fn y_range(data: &[[f64; 2]]) -> f64 {
+    let (min, max) = data
+        .iter()
+        .map(|[_, y]| *y)
+        .filter(|y| y.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), y| {
+            (min.min(y), max.max(y))
+        });
+    if min <= max {
+        max - min
+    } else {
+        0.0
+    }
+}
+
+/// keep the points of `data` that fulfill `keep`, together with their entries
+/// in `index`, unless it is empty
+fn retain_points(
+    data: &mut Vec<[f64; 2]>,
+    index: &mut Vec<usize>,
+    keep: impl Fn(&[f64; 2]) -> bool,
+) {
+    if !index.is_empty() {
+        let mut points = data.iter();
+        index.retain(|_| points.next().is_some_and(&keep));
+    }
+    data.retain(keep);
+}
+
+/// smallest and largest finite x-value, `None` without any
+fn x_bounds(data: &[[f64; 2]]) -> Option<(f64, f64)> {
+    let (min, max) = data
+        .iter()
+        .map(|[x, _]| *x)
+        .filter(|x| x.is_finite())
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), x| {
+            (min.min(x), max.max(x))
+        });
+    (min <= max).then_some((min, max))
+}
+
+/// insert a corner point between every pair of consecutive points
+fn staircase(points: &[[f64; 2]], mode: StepMode) -> Vec<[f64; 2]> {
+    let mut stairs = Vec::with_capacity(points.len() * 2);
+    for pair in points.windows(2) {
+        let ([x0, y0], [x1, y1]) = (pair[0], pair[1]);
+        stairs.push([x0, y0]);
+        match mode {
+            StepMode::StepBefore => stairs.push([x0, y1]),
+            StepMode::StepAfter => stairs.push([x1, y0]),
+            StepMode::None => (),
+        }
+    }
+    stairs.extend(points.last());
+    stairs
+}
+
+fn default_decibel_ref() -> f64 {
+    1.0
+}
+
+fn default_marker_size() -> f32 {
+    2.0
+}
+
+fn default_fill_alpha() -> f32 {
+    0.3
+}
+
+fn default_visible() -> bool {
+    true
+}
+
 impl FileEntry {
+    /// an idle entry, its scale and offsets start at `defaults`
+    pub fn new(
+        filename: String,
+        data_file: CSVFile,
+        id: usize,
+        defaults: &ManipulationDefaults,
+    ) -> Self {
+        FileEntry {
+            filename,
+            data_file,
+            state: FileEntryState::Idle,
+            scale: defaults.scale.clone(),
+            offset: defaults.offset.clone(),
+            xoffset: defaults.xoffset.clone(),
+            color: Color32::TRANSPARENT,
+            id,
+            preview: String::new(),
+            fit_linear: false,
+            cumulative_sum: false,
+            zero_at_start: false,
+            normalize_x: false,
+            decibel: false,
+            decibel_ref: default_decibel_ref(),
+            offset_mode: OffsetMode::default(),
+            show_original: false,
+            gap_threshold: None,
+            x_min: None,
+            x_max: None,
+            y_clamp: None,
+            clamp_mode: ClampMode::default(),
+            render_mode: RenderMode::default(),
+            step_mode: StepMode::default(),
+            marker_size: default_marker_size(),
+            fill: false,
+            fill_alpha: default_fill_alpha(),
+            fill_baseline: 0.0,
+            visible: true,
+            group: None,
+            tab: None,
+            options_seeded: false,
+            hovered_in_plot: false,
+            folder_label: None,
+            parsed_empty: false,
+            load_generation: 0,
+            stats_cache: None,
+            fill_cache: None,
+            fit_cache: None,
+            index_cache: None,
+        }
+    }
+    /// a plotted entry showing `data_file`, which was read elsewhere, e.g.
+    /// from stdin
+    pub fn with_data(filename: String, data_file: CSVFile, id: usize) -> Self {
+        let mut file_entry = Self::new(filename, data_file, id, &ManipulationDefaults::default());
+        file_entry.state = FileEntryState::Plotted;
+        file_entry
+    }
+    /// filename for labels and legends, with the folder if needed
+    pub fn display_name(&self) -> String {
+        match &self.folder_label {
+            Some(folder) => format!("{}/{}", folder, self.filename),
+            None => self.filename.clone(),
+        }
+    }
     pub fn get_file_label_text(&mut self) -> egui::RichText {
         use FileEntryState::*;
-        let text = egui::RichText::new(&self.filename);
+        let text = egui::RichText::new(self.display_name());
         match self.state {
             Idle | PreviouslyPlotted => text,
             Plotted => text.color(Color32::BLACK).background_color(self.color),
             Active => text
                 .color(Color32::BLACK.gamma_multiply(0.5))
                 .background_color(self.color),
+            NeedsConfig if self.parsed_empty => text.color(Color32::from_rgb(255, 140, 0)),
             NeedsConfig => text.color(Color32::RED),
+            Loading => text.italics(),
         }
     }
     pub fn get_file_label(&mut self) -> egui::Label {
         egui::Label::new(self.get_file_label_text())
     }
-    pub fn reload_csv(&mut self, folder_path: &Path, error_log: &mut Vec<String>) {
+    /// why a file needs configuration, shown next to its preview
+    pub fn needs_config_hint(&self) -> Option<&'static str> {
+        if self.state != FileEntryState::NeedsConfig {
+            return None;
+        }
+        Some(if self.parsed_empty {
+            "0 data points after parsing — check skip/columns"
+        } else {
+            "could not open the file — see the error log"
+        })
+    }
+    /// number of loaded data points, only for plotted files
+    pub fn get_point_count_text(&self) -> Option<egui::RichText> {
+        if !self.is_plotted() || self.data_file.data.is_empty() {
+            return None;
+        }
+        let count = match self.data_file.sampled {
+            Some(stride) => format!("({}, sampled 1:{})", self.data_file.data.len(), stride),
+            None => format!("({})", self.data_file.data.len()),
+        };
+        Some(egui::RichText::new(count).weak())
+    }
+    /// the 1-based file row of the point at `index` of the loaded data, if
+    /// the rows were kept while parsing
+    pub fn row_of(&self, index: usize) -> Option<usize> {
+        self.data_file.rows.get(index).copied()
+    }
+    /// how the lines of the file were parsed, only for loaded files
+    pub fn parse_stats_text(&self) -> Option<String> {
+        if self.data_file.data.is_empty() {
+            return None;
+        }
+        Some(self.data_file.parse_stats.summary())
+    }
+    /// absolute path of the file, relative folders are resolved against the
+    /// working directory
+    fn absolute_path(&self, folder_path: &Path) -> PathBuf {
+        let filepath = folder_path.join(&self.filename);
+        std::fs::canonicalize(&filepath).unwrap_or(filepath)
+    }
+    /// open the file with the program the system uses for it, e.g. an editor
+    pub fn open_externally(&self, folder_path: &Path) -> Result<(), String> {
+        let filepath = self.absolute_path(folder_path);
+        opener::open(&filepath)
+            .map_err(|err| format!("ERROR: could not open {:?}: {}", filepath, err))
+    }
+    /// show the file in the file manager of the system
+    pub fn reveal_in_file_manager(&self, folder_path: &Path) -> Result<(), String> {
+        let filepath = self.absolute_path(folder_path);
+        opener::reveal(&filepath).map_err(|err| {
+            format!(
+                "ERROR: could not show {:?} in the file manager: {}",
+                filepath, err
+            )
+        })
+    }
+    /// returns whether new data was read, the old data is kept otherwise
+    pub fn reload_csv(&mut self, folder_path: &Path, error_log: &mut Vec<String>) -> bool {
         let filepath = { folder_path.join(self.filename.clone()) };
-        if let Some(csvfile) = CSVFile::new(
-            filepath,
-            self.data_file.xcol,
-            self.data_file.ycol,
-            self.data_file.delimiter,
-            self.data_file.comment_char,
-            self.data_file.skip_header,
-            self.data_file.skip_footer,
-            error_log,
-        ) {
+        if let Some(csvfile) = CSVFile::new(filepath, &self.data_file, error_log) {
             self.data_file = csvfile;
+            self.stats_cache = None;
+            self.fill_cache = None;
+            self.fit_cache = None;
+            self.index_cache = None;
+            return true;
         }
+        false
+    }
+    pub fn matches_search_phrase(&self, search_phrase: &str) -> bool {
+        search_phrase
+            .split(" ")
+            .all(|phrase| self.filename.contains(phrase))
     }
     pub fn should_be_listed(&self, search_phrase: &str, folder_is_expanded: bool) -> bool {
         use FileEntryState::*;
-        let contains_search_phrase = search_phrase
-            .split(" ")
-            .all(|phrase| self.filename.contains(phrase));
+        let contains_search_phrase = self.matches_search_phrase(search_phrase);
         match (contains_search_phrase, folder_is_expanded, &self.state) {
             (true, true, _) => true,
             (_, _, Idle) => false,
-            (_, _, Plotted | PreviouslyPlotted | Active | NeedsConfig) => true,
+            (_, _, Plotted | PreviouslyPlotted | Active | NeedsConfig | Loading) => true,
         }
     }
+    pub fn is_loading(&self) -> bool {
+        self.state == FileEntryState::Loading
+    }
     pub fn is_active(&self) -> bool {
         self.state == FileEntryState::Active
     }
@@ -82,13 +511,258 @@ impl FileEntry {
         use FileEntryState::*;
         match self.state {
             Plotted | Active | NeedsConfig => true,
-            Idle | PreviouslyPlotted => false,
+            Idle | PreviouslyPlotted | Loading => false,
+        }
+    }
+    /// plotted and not hidden
+    pub fn is_drawn(&self) -> bool {
+        self.is_plotted() && self.visible
+    }
+    pub fn is_drawn_in(&self, tab: usize) -> bool {
+        self.is_drawn() && self.tab == Some(tab)
+    }
+    /// data with scale and offsets applied, as it is shown in the plot
+    pub fn transformed_data(&self) -> Vec<[f64; 2]> {
+        self.transform(false).0
+    }
+    /// `transformed_data` and the index into the loaded data of every point,
+    /// recomputed only if the transform changed
+    pub fn indexed_data(&mut self) -> (&[[f64; 2]], &[usize]) {
+        let key = self.transform_key();
+        match &self.index_cache {
+            Some((cached_key, ..)) if *cached_key == key => (),
+            _ => {
+                let (data, index) = self.transform(true);
+                self.index_cache = Some((key, data, index));
+            }
         }
+        match &self.index_cache {
+            Some((_, data, index)) => (data, index),
+            None => (&[], &[]),
+        }
+    }
+    /// the index is only tracked `with_index`, it is empty otherwise
+    fn transform(&self, with_index: bool) -> (Vec<[f64; 2]>, Vec<usize>) {
+        let scale = self.scale.parse().unwrap_or(1.0);
+        let offset = self.offset.parse().unwrap_or(0.0);
+        let xoffset = self.xoffset.parse().unwrap_or(0.0);
+        let (mut data, mut index) = self.summed_data(with_index);
+        if self.decibel {
+            // zeros have no level and are left out, see `num_zero_values`
+            let reference = self.decibel_ref.abs();
+            retain_points(&mut data, &mut index, |[_, y]| *y != 0.0);
+            for point in &mut data {
+                point[1] = 20.0 * (point[1].abs() / reference).log10();
+            }
+        }
+        let start = if self.zero_at_start {
+            self.start_value(&data).unwrap_or(0.0)
+        } else {
+            0.0
+        };
+        let offset = match self.offset_mode {
+            OffsetMode::Absolute => offset,
+            OffsetMode::FractionOfRange => offset * y_range(&data) * scale.abs(),
+        };
+        // a zero x-range is left as it is, see `has_x_range`
+        let (xmin, xspan) = match x_bounds(&data) {
+            Some((xmin, xmax)) if self.normalize_x && xmax > xmin => (xmin, xmax - xmin),
+            _ => (0.0, 1.0),
+        };
+        for [x, y] in &mut data {
+            *x = (*x - xmin) / xspan + xoffset;
+            *y = (*y - start) * scale + offset;
+        }
+        match (self.y_clamp, self.clamp_mode) {
+            (None, _) => (),
+            (Some((lo, hi)), ClampMode::Clip) => {
+                for [_, y] in &mut data {
+                    if y.is_finite() {
+                        *y = y.clamp(lo, hi);
+                    }
+                }
+            }
+            (Some((lo, hi)), ClampMode::Drop) => {
+                retain_points(&mut data, &mut index, |[_, y]| !(*y < lo || *y > hi))
+            }
+        }
+        (data, index)
+    }
+    /// the data within `x_min` and `x_max`, with the running sum of y if
+    /// `cumulative_sum` is set, the first step of `transformed_data`
+    fn summed_data(&self, with_index: bool) -> (Vec<[f64; 2]>, Vec<usize>) {
+        let mut sum = 0.0;
+        let mut index = vec![];
+        let data = self
+            .data_file
+            .data
+            .iter()
+            .enumerate()
+            .filter(|(_, [x, _])| {
+                !self.x_min.is_some_and(|min| *x < min) && !self.x_max.is_some_and(|max| *x > max)
+            })
+            .map(|(i, [x, y])| {
+                if with_index {
+                    index.push(i);
+                }
+                let y = if self.cumulative_sum && y.is_finite() {
+                    sum += y;
+                    sum
+                } else {
+                    *y
+                };
+                [*x, y]
+            })
+            .collect();
+        (data, index)
+    }
+    /// number of y-values that are zero when converted to decibel
+    pub fn num_zero_values(&self) -> usize {
+        self.summed_data(false)
+            .0
+            .iter()
+            .filter(|[_, y]| *y == 0.0)
+            .count()
+    }
+    /// the first finite y-value, which `zero_at_start` subtracts
+    fn start_value(&self, data: &[[f64; 2]]) -> Option<f64> {
+        data.iter().map(|[_, y]| *y).find(|y| y.is_finite())
+    }
+    /// whether `zero_at_start` has a point to refer to
+    pub fn has_start_value(&self) -> bool {
+        self.start_value(&self.data_file.data).is_some()
+    }
+    /// smallest and largest finite x-value of the loaded data
+    pub fn data_x_bounds(&self) -> Option<(f64, f64)> {
+        x_bounds(&self.data_file.data)
+    }
+    /// whether the x-values span a range that `normalize_x` can map onto 0..1
+    pub fn has_x_range(&self) -> bool {
+        x_bounds(&self.data_file.data).is_some_and(|(xmin, xmax)| xmax > xmin)
+    }
+    /// transformed data, split into separately drawn segments at x-gaps
+    /// larger than `gap_threshold` and expanded into a staircase according
+    /// to `step_mode`
+    pub fn line_segments(&self) -> Vec<Vec<[f64; 2]>> {
+        let data = self.transformed_data();
+        let segments = match self.gap_threshold {
+            None => vec![data],
+            Some(threshold) => {
+                let mut segments = vec![];
+                let mut segment: Vec<[f64; 2]> = vec![];
+                for point in data {
+                    if let Some(last) = segment.last() {
+                        if (point[0] - last[0]).abs() > threshold {
+                            segments.push(std::mem::take(&mut segment));
+                        }
+                    }
+                    segment.push(point);
+                }
+                segments.push(segment);
+                segments
+            }
+        };
+        match self.step_mode {
+            StepMode::None => segments,
+            mode => segments
+                .into_iter()
+                .map(|segment| staircase(&segment, mode))
+                .collect(),
+        }
+    }
+    /// convex polygons that together make up the area between the line
+    /// segments and `fill_baseline`, intervals crossing the baseline are
+    /// split into two triangles at the crossing, rebuilt only if the line changed
+    pub fn fill_polygons(&mut self) -> &[Vec<[f64; 2]>] {
+        let key = format!(
+            "{}|{}|{:?}|{}",
+            self.transform_key(),
+            self.fill_baseline,
+            self.gap_threshold,
+            self.step_mode.label()
+        );
+        match &self.fill_cache {
+            Some((cached_key, _)) if *cached_key == key => (),
+            _ => self.fill_cache = Some((key, self.build_fill_polygons())),
+        }
+        self.fill_cache
+            .as_ref()
+            .map(|(_, polygons)| polygons.as_slice())
+            .unwrap_or_default()
+    }
+    fn build_fill_polygons(&self) -> Vec<Vec<[f64; 2]>> {
+        let base = self.fill_baseline;
+        let mut polygons = vec![];
+        for segment in self.line_segments() {
+            let finite: Vec<[f64; 2]> = segment
+                .into_iter()
+                .filter(|[x, y]| x.is_finite() && y.is_finite())
+                .collect();
+            for pair in finite.windows(2) {
+                let ([xa, ya], [xb, yb]) = (pair[0], pair[1]);
+                if (ya - base) * (yb - base) < 0.0 {
+                    let xc = xa + (xb - xa) * (base - ya) / (yb - ya);
+                    polygons.push(vec![[xa, base], [xc, base], [xa, ya]]);
+                    polygons.push(vec![[xc, base], [xb, base], [xb, yb]]);
+                } else {
+                    polygons.push(vec![[xa, base], [xb, base], [xb, yb], [xa, ya]]);
+                }
+            }
+        }
+        polygons
+    }
+    /// identifies the transform applied in `transformed_data`
+    pub fn transform_key(&self) -> String {
+        format!(
+            "{}|{}|{}|{}|{}|{}|{}|{}|{}|{}|{:?}|{}|{:?}|{:?}",
+            self.data_file.data.len(),
+            self.scale.input,
+            self.offset.input,
+            self.xoffset.input,
+            self.cumulative_sum,
+            self.zero_at_start,
+            self.normalize_x,
+            self.decibel,
+            self.decibel_ref,
+            self.offset_mode.label(),
+            self.y_clamp,
+            self.clamp_mode.label(),
+            self.x_min,
+            self.x_max
+        )
+    }
+    /// statistics of the transformed data, recomputed only if the transform changed
+    pub fn stats(&mut self) -> Option<Stats> {
+        let key = self.transform_key();
+        match &self.stats_cache {
+            Some((cached_key, stats)) if *cached_key == key => *stats,
+            _ => {
+                let stats = stats(&self.transformed_data());
+                self.stats_cache = Some((key, stats));
+                stats
+            }
+        }
+    }
+    /// linear fit of the transformed data, recomputed only if the transform changed
+    pub fn linear_fit(&mut self) -> Option<LinearFit> {
+        let key = self.transform_key();
+        match &self.fit_cache {
+            Some((cached_key, fit)) if *cached_key == key => *fit,
+            _ => {
+                let fit = linear_fit(&self.transformed_data());
+                self.fit_cache = Some((key, fit));
+                fit
+            }
+        }
+    }
+    /// points of the fit line spanning the x-range of the transformed data
+    pub fn linear_fit_line(&mut self) -> Option<[[f64; 2]; 2]> {
+        self.linear_fit().map(|fit| fit.line())
     }
     pub fn was_just_plotted(&self) -> bool {
         use FileEntryState::*;
         match self.state {
-            Idle | Plotted | Active | NeedsConfig => true,
+            Idle | Plotted | Active | NeedsConfig | Loading => true,
             PreviouslyPlotted => true,
         }
     }
@@ -96,25 +770,12 @@ impl FileEntry {
 
 // transitions
 impl FileEntry {
-    pub fn clicked(&mut self, path: &Path, error_log: &mut Vec<String>) {
-        if self.data_file.data.is_empty() && self.state != FileEntryState::NeedsConfig {
-            let filepath = { path.join(self.filename.clone()) };
-            if let Some(csvfile) = CSVFile::new(
-                filepath,
-                self.data_file.xcol,
-                self.data_file.ycol,
-                self.data_file.delimiter,
-                self.data_file.comment_char,
-                self.data_file.skip_header,
-                self.data_file.skip_footer,
-                error_log,
-            ) {
-                // immediately plot freshly loaded csv
-                self.state = FileEntryState::Plotted;
-                self.data_file = csvfile;
-            } else {
-                self.state = FileEntryState::NeedsConfig;
-            }
+    pub fn clicked(&mut self, path: &Path, default_options: &CSVFile, loader: &mut Loader) {
+        if self.data_file.data.is_empty()
+            && self.state != FileEntryState::NeedsConfig
+            && self.state != FileEntryState::Loading
+        {
+            self.load(path, default_options, loader);
         } else {
             self.state = match self.state {
                 FileEntryState::Active | FileEntryState::Plotted => {
@@ -122,14 +783,130 @@ impl FileEntry {
                 }
                 FileEntryState::Idle | FileEntryState::PreviouslyPlotted => FileEntryState::Plotted,
                 FileEntryState::NeedsConfig => FileEntryState::Idle,
+                FileEntryState::Loading => FileEntryState::Loading,
             }
         }
     }
-    pub fn secondary_clicked(&mut self) {
+    /// parse in the background, `finish_loading` is called with the result
+    fn load(&mut self, path: &Path, default_options: &CSVFile, loader: &mut Loader) {
+        // the folder defaults apply to the first load only, so they do not
+        // override options the user adjusted afterwards
+        if !self.options_seeded {
+            self.data_file.apply_options(default_options);
+            self.options_seeded = true;
+        }
+        let filepath = { path.join(self.filename.clone()) };
+        self.load_generation = loader.load(self.id, filepath, self.data_file.options());
+        self.state = FileEntryState::Loading;
+    }
+    /// take over the result of the load of `generation`, `parsed_empty` tells
+    /// a file without data points apart from one that could not be read,
+    /// returns false if the result is outdated
+    pub fn finish_loading(
+        &mut self,
+        generation: usize,
+        csvfile: Option<CSVFile>,
+        parsed_empty: bool,
+    ) -> bool {
+        if self.state != FileEntryState::Loading || generation != self.load_generation {
+            return false; // loading was cancelled or restarted
+        }
+        self.parsed_empty = parsed_empty;
+        if let Some(csvfile) = csvfile {
+            // immediately plot freshly loaded csv
+            self.state = FileEntryState::Plotted;
+            self.data_file = csvfile;
+            self.stats_cache = None;
+            self.fill_cache = None;
+            self.fit_cache = None;
+            self.index_cache = None;
+        } else {
+            self.state = FileEntryState::NeedsConfig;
+        }
+        true
+    }
+    pub fn cancel_loading(&mut self) {
+        if self.state == FileEntryState::Loading {
+            self.state = FileEntryState::Idle;
+        }
+    }
+    /// plot the file, loading its data in the background if necessary
+    pub fn plot(&mut self, path: &Path, default_options: &CSVFile, loader: &mut Loader) {
+        use FileEntryState::*;
         match self.state {
-            FileEntryState::Plotted => self.state = FileEntryState::Active,
-            FileEntryState::Active => self.state = FileEntryState::Plotted,
-            _ => (),
+            Plotted | Active | Loading => (),
+            _ if self.data_file.data.is_empty() => self.load(path, default_options, loader),
+            Idle | PreviouslyPlotted | NeedsConfig => self.state = Plotted,
+        }
+    }
+    pub fn hide(&mut self) {
+        self.state = FileEntryState::Idle;
+    }
+    pub fn state(&self) -> FileEntryState {
+        self.state
+    }
+    pub fn restore_state(&mut self, state: FileEntryState) {
+        self.state = state;
+    }
+    pub fn manipulations(&self) -> Manipulations {
+        Manipulations {
+            scale: self.scale.clone(),
+            offset: self.offset.clone(),
+            xoffset: self.xoffset.clone(),
+            cumulative_sum: self.cumulative_sum,
+            zero_at_start: self.zero_at_start,
+            normalize_x: self.normalize_x,
+            decibel: self.decibel,
+            offset_mode: self.offset_mode,
+            fit_linear: self.fit_linear,
+            show_original: self.show_original,
+            gap_threshold: self.gap_threshold,
+            step_mode: self.step_mode,
+            y_clamp: self.y_clamp,
+            clamp_mode: self.clamp_mode,
+            x_min: self.x_min,
+            x_max: self.x_max,
+        }
+    }
+    pub fn set_manipulations(&mut self, manipulations: Manipulations) {
+        let Manipulations {
+            scale,
+            offset,
+            xoffset,
+            cumulative_sum,
+            zero_at_start,
+            normalize_x,
+            decibel,
+            offset_mode,
+            fit_linear,
+            show_original,
+            gap_threshold,
+            step_mode,
+            y_clamp,
+            clamp_mode,
+            x_min,
+            x_max,
+        } = manipulations;
+        self.scale = scale;
+        self.offset = offset;
+        self.xoffset = xoffset;
+        self.cumulative_sum = cumulative_sum;
+        self.zero_at_start = zero_at_start;
+        self.normalize_x = normalize_x;
+        self.decibel = decibel;
+        self.offset_mode = offset_mode;
+        self.fit_linear = fit_linear;
+        self.show_original = show_original;
+        self.gap_threshold = gap_threshold;
+        self.step_mode = step_mode;
+        self.y_clamp = y_clamp;
+        self.clamp_mode = clamp_mode;
+        self.x_min = x_min;
+        self.x_max = x_max;
+    }
+    pub fn deactivate(&mut self) {
+        if self.state == FileEntryState::Active {
+            self.state = FileEntryState::Plotted
         }
     }
     pub fn search_phrase_changed(&mut self) {
@@ -140,41 +917,48 @@ impl FileEntry {
             Plotted => Plotted,
             Active => Active,
             NeedsConfig => NeedsConfig,
+            Loading => Loading,
         }
     }
 }
 
-pub fn get_file_entries(folder: &Path, id_counter: &mut usize) -> Vec<FileEntry> {
+/// entries for the files in `folder`, their scale and offsets start at `defaults`
+pub fn get_file_entries(
+    folder: &Path,
+    id_counter: &mut usize,
+    defaults: &ManipulationDefaults,
+    error_log: &mut Vec<String>,
+) -> Vec<FileEntry> {
     let mut file_entries = vec![];
-    if let Ok(read_dir) = folder.read_dir() {
-        // flatten pulls out the Ok variants of the `read_dir` elements
-        for entry in read_dir.into_iter().flatten() {
-            // only list csv files
-            let filename = entry.file_name().to_string_lossy().into_owned();
-            let data_file = CSVFile {
-                filepath: filename.clone().into(),
-                ..Default::default()
-            };
-            let file_entry = FileEntry {
-                filename,
-                data_file,
-                state: FileEntryState::Idle,
-                scale: FloatInput {
-                    input: "1.0".to_string(),
-                },
-                offset: FloatInput {
-                    input: "0.0".to_string(),
-                },
-                xoffset: FloatInput {
-                    input: "0.0".to_string(),
-                },
-                color: Color32::TRANSPARENT,
-                id: *id_counter,
-                preview: utils::read_first_lines(&entry.path(), 20).unwrap_or_default(),
-            };
-            *id_counter += 1;
-            file_entries.push(file_entry)
+    let read_dir = match folder.read_dir() {
+        Ok(read_dir) => read_dir,
+        Err(err) => {
+            error_log.push(format!("ERROR: could not read folder {folder:?}: {}", err));
+            return file_entries;
         }
+    };
+    for entry in read_dir {
+        // e.g. permission denied, the other entries are still listed
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(err) => {
+                error_log.push(format!(
+                    "WARNING: skipped an entry of folder {folder:?}: {}",
+                    err
+                ));
+                continue;
+            }
+        };
+        // only list csv files
+        let filename = entry.file_name().to_string_lossy().into_owned();
+        let data_file = CSVFile {
+            filepath: filename.clone().into(),
+            ..Default::default()
+        };
+        let mut file_entry = FileEntry::new(filename, data_file, *id_counter, defaults);
+        file_entry.preview = utils::read_first_lines(&entry.path(), 20).unwrap_or_default();
+        *id_counter += 1;
+        file_entries.push(file_entry)
     }
     file_entries
 }
@@ -192,12 +976,179 @@ mod utils {
         let buf_reader = BufReader::new(file);
         let mut lines = String::new();
 
-        for line in buf_reader.lines().take(num_lines) {
-            if let Ok(line) = line {
-                lines.push_str(&line);
-            }
+        for line in buf_reader.lines().take(num_lines).map_while(Result::ok) {
+            lines.push_str(&line);
+            lines.push('\n');
         }
 
         Ok(lines)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        event::{AppEvent, ResetManipulations},
+        folder::Folder,
+        App,
+    };
+
+    fn file_entry() -> FileEntry {
+        // tests run in parallel, each on its own thread
+        let thread = format!("{:?}", std::thread::current().id());
+        let thread: String = thread.chars().filter(char::is_ascii_digit).collect();
+        let dir = std::env::temp_dir().join(format!("plotme_entry_{thread}"));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("data.csv"), "").unwrap();
+        let mut file_entry =
+            get_file_entries(&dir, &mut 0, &ManipulationDefaults::default(), &mut vec![])
+                .pop()
+                .unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+        file_entry.data_file.data = vec![[0.0, 0.0], [1.0, 1.0], [2.0, 4.0]];
+        file_entry.state = FileEntryState::Plotted;
+        file_entry
+    }
+
+    fn input(input: &str) -> FloatInput {
+        FloatInput {
+            input: input.into(),
+        }
+    }
+
+    #[test]
+    fn reset_and_undo_cover_every_manipulation() {
+        let mut file_entry = file_entry();
+        file_entry.scale = input("2.0");
+        file_entry.offset = input("1.0");
+        file_entry.xoffset = input("3.0");
+        file_entry.cumulative_sum = true;
+        file_entry.zero_at_start = true;
+        file_entry.normalize_x = true;
+        file_entry.decibel = true;
+        file_entry.offset_mode = OffsetMode::FractionOfRange;
+        file_entry.fit_linear = true;
+        file_entry.show_original = true;
+        file_entry.gap_threshold = Some(0.5);
+        file_entry.step_mode = StepMode::StepAfter;
+        file_entry.y_clamp = Some((0.0, 2.0));
+        file_entry.clamp_mode = ClampMode::Drop;
+        file_entry.x_min = Some(0.5);
+        file_entry.x_max = Some(1.5);
+        let mut app = App::default();
+        app.folders = vec![Folder::new("data".into(), vec![file_entry])];
+        let defaults = ManipulationDefaults {
+            scale: input("0.5"),
+            offset: input("0.1"),
+            xoffset: input("0.2"),
+        };
+        let mut reset = ResetManipulations::new(&defaults);
+
+        reset.apply(&mut app);
+        let file_entry = &app.folders[0].files[0];
+        assert_eq!(file_entry.scale.input, "0.5");
+        assert_eq!(file_entry.offset.input, "0.1");
+        assert_eq!(file_entry.xoffset.input, "0.2");
+        assert!(!file_entry.cumulative_sum);
+        assert!(!file_entry.zero_at_start);
+        assert!(!file_entry.normalize_x);
+        assert!(!file_entry.decibel);
+        assert!(file_entry.offset_mode == OffsetMode::default());
+        assert!(!file_entry.fit_linear);
+        assert!(!file_entry.show_original);
+        assert_eq!(file_entry.gap_threshold, None);
+        assert!(file_entry.step_mode == StepMode::default());
+        assert_eq!(file_entry.y_clamp, None);
+        assert!(file_entry.clamp_mode == ClampMode::default());
+        assert_eq!((file_entry.x_min, file_entry.x_max), (None, None));
+
+        reset.undo(&mut app);
+        let file_entry = &app.folders[0].files[0];
+        assert_eq!(file_entry.scale.input, "2.0");
+        assert_eq!(file_entry.offset.input, "1.0");
+        assert_eq!(file_entry.xoffset.input, "3.0");
+        assert!(file_entry.cumulative_sum);
+        assert!(file_entry.zero_at_start);
+        assert!(file_entry.normalize_x);
+        assert!(file_entry.decibel);
+        assert!(file_entry.offset_mode == OffsetMode::FractionOfRange);
+        assert!(file_entry.fit_linear);
+        assert!(file_entry.show_original);
+        assert_eq!(file_entry.gap_threshold, Some(0.5));
+        assert!(file_entry.step_mode == StepMode::StepAfter);
+        assert_eq!(file_entry.y_clamp, Some((0.0, 2.0)));
+        assert!(file_entry.clamp_mode == ClampMode::Drop);
+        assert_eq!((file_entry.x_min, file_entry.x_max), (Some(0.5), Some(1.5)));
+    }
+
+    #[test]
+    fn linear_fit_follows_the_transform() {
+        let dir = std::env::temp_dir().join("plotme_linear_fit");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("data.csv"), "x,y\n").unwrap();
+        let mut file_entry =
+            get_file_entries(&dir, &mut 0, &ManipulationDefaults::default(), &mut vec![])
+                .pop()
+                .unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+        file_entry.data_file.data = vec![[0.0, 0.0], [1.0, 1.0], [2.0, 4.0]];
+        assert_eq!(file_entry.linear_fit().unwrap().slope, 2.0);
+        let [[x0, _], [x1, _]] = file_entry.linear_fit_line().unwrap();
+        assert_eq!((x0, x1), (0.0, 2.0));
+        file_entry.scale.input = "2".into();
+        assert_eq!(file_entry.linear_fit().unwrap().slope, 4.0);
+    }
+
+    #[test]
+    fn results_of_restarted_loads_are_dropped() {
+        let dir = std::env::temp_dir().join("plotme_restarted_load");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("data.csv"), "x,y\n1,2\n").unwrap();
+        let mut file_entry =
+            get_file_entries(&dir, &mut 0, &ManipulationDefaults::default(), &mut vec![])
+                .pop()
+                .unwrap();
+        let mut loader = Loader::default();
+        file_entry.load(&dir, &CSVFile::default(), &mut loader);
+        file_entry.load(&dir, &CSVFile::default(), &mut loader);
+        let mut results = vec![];
+        while results.len() < 2 {
+            results.extend(loader.finished());
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+        results.sort_by_key(|result| result.generation);
+        let [first, second] = [results.remove(0), results.remove(0)];
+        assert!(!file_entry.finish_loading(first.generation, first.csvfile, false));
+        assert!(file_entry.state() == FileEntryState::Loading);
+        assert!(file_entry.finish_loading(second.generation, second.csvfile, false));
+        assert!(file_entry.state() == FileEntryState::Plotted);
+    }
+
+    #[test]
+    fn fill_polygons_follow_the_baseline() {
+        let mut file_entry = file_entry();
+        assert_eq!(file_entry.fill_polygons().len(), 2);
+        // the second interval crosses the new baseline and is split in two
+        file_entry.fill_baseline = 2.0;
+        assert_eq!(file_entry.fill_polygons().len(), 3);
+        file_entry.gap_threshold = Some(0.5);
+        assert!(file_entry.fill_polygons().is_empty());
+    }
+
+    #[test]
+    fn indexed_data_follows_the_transform() {
+        let mut file_entry = file_entry();
+        file_entry.x_min = Some(0.5);
+        assert_eq!(
+            file_entry.indexed_data(),
+            (&[[1.0, 1.0], [2.0, 4.0]][..], &[1, 2][..])
+        );
+        file_entry.scale.input = "2".into();
+        assert_eq!(
+            file_entry.indexed_data(),
+            (&[[1.0, 2.0], [2.0, 8.0]][..], &[1, 2][..])
+        );
+    }
+}
@@ -0,0 +1,260 @@
+// fits and summary statistics of (transformed) file data
+
+use serde::{Deserialize, Serialize};
+
+use crate::transform::{even_grid, resample_linear, sorted_finite};
+
+#[derive(Clone, Copy)]
+pub struct LinearFit {
+    pub slope: f64,
+    pub intercept: f64,
+    pub r_squared: f64,
+    /// x-range of the fitted points
+    pub xmin: f64,
+    pub xmax: f64,
+}
+
+impl LinearFit {
+    pub fn eval(&self, x: f64) -> f64 {
+        self.slope * x + self.intercept
+    }
+    /// points of the line spanning the x-range of the fitted points
+    pub fn line(&self) -> [[f64; 2]; 2] {
+        [
+            [self.xmin, self.eval(self.xmin)],
+            [self.xmax, self.eval(self.xmax)],
+        ]
+    }
+}
+
+fn is_finite([x, y]: &[f64; 2]) -> bool {
+    x.is_finite() && y.is_finite()
+}
+
+/// the fit ignores non-finite points
+pub fn linear_fit(data: &[[f64; 2]]) -> Option<LinearFit> {
+    let data: Vec<[f64; 2]> = data.iter().copied().filter(is_finite).collect();
+    if data.len() < 2 {
+        return None;
+    }
+    let n = data.len() as f64;
+    let xmean = data.iter().map(|[x, _]| x).sum::<f64>() / n;
+    let ymean = data.iter().map(|[_, y]| y).sum::<f64>() / n;
+    let (mut sxx, mut sxy, mut syy) = (0.0, 0.0, 0.0);
+    let (mut xmin, mut xmax) = (f64::INFINITY, f64::NEG_INFINITY);
+    for [x, y] in &data {
+        let (dx, dy) = (x - xmean, y - ymean);
+        sxx += dx * dx;
+        sxy += dx * dy;
+        syy += dy * dy;
+        xmin = xmin.min(*x);
+        xmax = xmax.max(*x);
+    }
+    // all x values identical, slope is undefined
+    if sxx == 0.0 {
+        return None;
+    }
+    let slope = sxy / sxx;
+    let intercept = ymean - slope * xmean;
+    // a perfectly flat line is perfectly described by the fit
+    let r_squared = if syy == 0.0 {
+        1.0
+    } else {
+        sxy * sxy / (sxx * syy)
+    };
+    Some(LinearFit {
+        slope,
+        intercept,
+        r_squared,
+        xmin,
+        xmax,
+    })
+}
+
+#[derive(Clone, Copy)]
+pub struct Stats {
+    pub ymin: f64,
+    pub ymax: f64,
+    pub ymean: f64,
+    pub xmin: f64,
+    pub xmax: f64,
+}
+
+/// the statistics ignore non-finite points
+pub fn stats(data: &[[f64; 2]]) -> Option<Stats> {
+    let data: Vec<[f64; 2]> = data.iter().copied().filter(is_finite).collect();
+    if data.is_empty() {
+        return None;
+    }
+    let mut stats = Stats {
+        ymin: f64::INFINITY,
+        ymax: f64::NEG_INFINITY,
+        ymean: 0.0,
+        xmin: f64::INFINITY,
+        xmax: f64::NEG_INFINITY,
+    };
+    for [x, y] in &data {
+        stats.xmin = stats.xmin.min(*x);
+        stats.xmax = stats.xmax.max(*x);
+        stats.ymin = stats.ymin.min(*y);
+        stats.ymax = stats.ymax.max(*y);
+        stats.ymean += y;
+    }
+    stats.ymean /= data.len() as f64;
+    Some(stats)
+}
+
+/// x-range of the grid that several series are averaged on
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AverageRange {
+    /// only where all series have data
+    #[default]
+    Intersection,
+    /// wherever at least one series has data
+    Union,
+}
+
+impl AverageRange {
+    pub const ALL: [AverageRange; 2] = [Self::Intersection, Self::Union];
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Intersection => "Intersection of x-ranges",
+            Self::Union => "Union of x-ranges",
+        }
+    }
+}
+
+// upper limit of the number of points of an averaged curve
+const MAX_AVERAGE_POINTS: usize = 10_000;
+
+/// `[x, mean, standard deviation]` of `series`, linearly interpolated onto an
+/// evenly spaced grid, series only contribute within their own x-range
+pub fn average(series: &[Vec<[f64; 2]>], range: AverageRange) -> Option<Vec<[f64; 3]>> {
+    let series: Vec<Vec<[f64; 2]>> = series
+        .iter()
+        .map(|data| sorted_finite(data))
+        .filter(|data| data.len() >= 2)
+        .collect();
+    if series.len() < 2 {
+        return None;
+    }
+    let ranges = series
+        .iter()
+        .map(|data| (data[0][0], data[data.len() - 1][0]));
+    let (x0, x1) = match range {
+        AverageRange::Intersection => ranges.fold((f64::NEG_INFINITY, f64::INFINITY), |acc, r| {
+            (acc.0.max(r.0), acc.1.min(r.1))
+        }),
+        AverageRange::Union => ranges.fold((f64::INFINITY, f64::NEG_INFINITY), |acc, r| {
+            (acc.0.min(r.0), acc.1.max(r.1))
+        }),
+    };
+    if x0 >= x1 {
+        return None;
+    }
+    let num_points = series
+        .iter()
+        .map(|data| data.len())
+        .max()
+        .unwrap_or(2)
+        .clamp(2, MAX_AVERAGE_POINTS);
+    let grid = even_grid(x0, x1, num_points);
+    let resampled: Vec<Vec<f64>> = series
+        .iter()
+        .map(|data| resample_linear(data, &grid))
+        .collect();
+    let mut averaged = Vec::with_capacity(num_points);
+    for (i, &x) in grid.iter().enumerate() {
+        let values: Vec<f64> = resampled
+            .iter()
+            .map(|ys| ys[i])
+            .filter(|y| !y.is_nan())
+            .collect();
+        if values.is_empty() {
+            continue;
+        }
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<f64>() / n;
+        let variance = values.iter().map(|y| (y - mean).powi(2)).sum::<f64>() / n;
+        averaged.push([x, mean, variance.sqrt()]);
+    }
+    Some(averaged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f64, b: f64) {
+        assert!((a - b).abs() < 1e-12, "{a} != {b}");
+    }
+
+    #[test]
+    fn exact_line() {
+        let data = [[0.0, 1.0], [1.0, 3.0], [2.0, 5.0], [3.0, 7.0]];
+        let fit = linear_fit(&data).unwrap();
+        assert_close(fit.slope, 2.0);
+        assert_close(fit.intercept, 1.0);
+        assert_close(fit.r_squared, 1.0);
+        assert_close(fit.eval(10.0), 21.0);
+        assert_eq!(fit.line(), [[0.0, 1.0], [3.0, 7.0]]);
+    }
+
+    #[test]
+    fn scattered_points() {
+        let data = [
+            [0.0, 0.0],
+            [1.0, 1.0],
+            [f64::NAN, 5.0],
+            [2.0, 0.0],
+            [3.0, 1.0],
+        ];
+        let fit = linear_fit(&data).unwrap();
+        assert_close(fit.slope, 0.2);
+        assert_close(fit.intercept, 0.2);
+        assert_close(fit.r_squared, 0.2);
+    }
+
+    #[test]
+    fn identical_x_values_have_no_fit() {
+        assert!(linear_fit(&[[1.0, 0.0], [1.0, 2.0]]).is_none());
+        assert!(linear_fit(&[[1.0, 0.0]]).is_none());
+        assert!(linear_fit(&[]).is_none());
+    }
+
+    #[test]
+    fn flat_line() {
+        let fit = linear_fit(&[[0.0, 3.0], [1.0, 3.0], [2.0, 3.0]]).unwrap();
+        assert_close(fit.slope, 0.0);
+        assert_close(fit.intercept, 3.0);
+        assert_close(fit.r_squared, 1.0);
+    }
+
+    #[test]
+    fn average_on_the_intersection_and_the_union() {
+        let series = [vec![[0.0, 0.0], [2.0, 2.0]], vec![[3.0, 4.0], [1.0, 2.0]]];
+        let averaged = average(&series, AverageRange::Intersection).unwrap();
+        assert_eq!(averaged, vec![[1.0, 1.5, 0.5], [2.0, 2.5, 0.5]]);
+        // outside of the intersection, only one series contributes
+        let averaged = average(&series, AverageRange::Union).unwrap();
+        assert_eq!(averaged, vec![[0.0, 0.0, 0.0], [3.0, 4.0, 0.0]]);
+    }
+
+    #[test]
+    fn average_of_series_that_do_not_overlap() {
+        let series = [
+            vec![[0.0, 0.0], [0.5, 0.0], [1.0, 0.0]],
+            vec![[2.0, 2.0], [2.5, 2.0], [3.0, 2.0]],
+        ];
+        assert!(average(&series, AverageRange::Intersection).is_none());
+        // the gap between the series is left out
+        let averaged = average(&series, AverageRange::Union).unwrap();
+        assert_eq!(averaged, vec![[0.0, 0.0, 0.0], [3.0, 2.0, 0.0]]);
+    }
+
+    #[test]
+    fn average_needs_two_series() {
+        let series = [vec![[0.0, 0.0], [1.0, 1.0]], vec![[f64::NAN, 0.0]]];
+        assert!(average(&series, AverageRange::Union).is_none());
+    }
+}
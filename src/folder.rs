@@ -1,9 +1,16 @@
-use std::path::PathBuf;
+use std::{
+    cmp::Ordering,
+    path::{Path, PathBuf},
+};
 
 use egui::Widget;
 use serde::{Deserialize, Serialize};
 
-use crate::file_entry::FileEntry;
+use crate::{
+    csvfile::{preview_fields, CSVFile},
+    file_entry::FileEntry,
+    loader::Loader,
+};
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Folder {
@@ -11,38 +18,445 @@ pub struct Folder {
     pub files: Vec<FileEntry>,
     pub expanded: bool,
     pub to_be_deleted: bool,
+    /// parse options for files of this folder that are loaded the first time
+    #[serde(default)]
+    pub default_options: CSVFile,
+    /// groups whose files are hidden in the tree
+    #[serde(default)]
+    collapsed_groups: Vec<String>,
+    // indices into `files` in listing order and the mode they were sorted by
+    #[serde(skip)]
+    order: Option<(FileSort, Vec<usize>)>,
+}
+
+/// order of the files in the tree, the `files` vector itself is not reordered
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FileSort {
+    #[default]
+    Name,
+    /// numbers in names are compared by value, `file2` comes before `file10`
+    NameNatural,
+    ModifiedTime,
+    Size,
+}
+
+impl FileSort {
+    pub const ALL: [FileSort; 4] = [
+        Self::Name,
+        Self::NameNatural,
+        Self::ModifiedTime,
+        Self::Size,
+    ];
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Name => "Name",
+            Self::NameNatural => "Name (natural)",
+            Self::ModifiedTime => "Modified time",
+            Self::Size => "Size",
+        }
+    }
+}
+
+/// when the labels of files show the folder in front of the filename
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FileLabels {
+    Filename,
+    /// only for plotted files whose filename is also plotted from another folder
+    #[default]
+    QualifyDuplicates,
+    WithFolder,
+}
+
+impl FileLabels {
+    pub const ALL: [FileLabels; 3] = [Self::Filename, Self::QualifyDuplicates, Self::WithFolder];
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Filename => "Filename",
+            Self::QualifyDuplicates => "Folder for duplicate names",
+            Self::WithFolder => "Folder and filename",
+        }
+    }
+}
+
+/// the files and whether they are plotted, the labels set by
+/// `qualify_file_labels` only change with them and the mode
+pub fn file_labels_key(folders: &[Folder]) -> Vec<(usize, bool)> {
+    folders
+        .iter()
+        .flat_map(|folder| &folder.files)
+        .map(|file_entry| (file_entry.id, file_entry.is_plotted()))
+        .collect()
+}
+
+/// set the folders shown in the labels of the files according to `mode`,
+/// duplicates use the name of their folder, or its full path if that is
+/// not unique either
+pub fn qualify_file_labels(folders: &mut [Folder], mode: FileLabels) {
+    let plotted_names: Vec<(&str, &Path)> = folders
+        .iter()
+        .flat_map(|folder| {
+            folder
+                .files
+                .iter()
+                .filter(|file_entry| file_entry.is_plotted())
+                .map(|file_entry| (file_entry.filename.as_str(), folder.path.as_path()))
+        })
+        .collect();
+    let folder_name = |path: &Path| {
+        path.file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.to_string_lossy().to_string())
+    };
+    let mut labels = Vec::new();
+    for folder in folders.iter() {
+        for file_entry in &folder.files {
+            let duplicates: Vec<&Path> = plotted_names
+                .iter()
+                .filter(|(name, path)| *name == file_entry.filename && *path != folder.path)
+                .map(|(_, path)| *path)
+                .collect();
+            let label = match mode {
+                FileLabels::Filename => None,
+                FileLabels::QualifyDuplicates
+                    if duplicates.is_empty() || !file_entry.is_plotted() =>
+                {
+                    None
+                }
+                FileLabels::QualifyDuplicates => {
+                    let name = folder_name(&folder.path);
+                    if duplicates.iter().any(|path| folder_name(path) == name) {
+                        Some(folder.path.to_string_lossy().to_string())
+                    } else {
+                        Some(name)
+                    }
+                }
+                FileLabels::WithFolder => Some(folder_name(&folder.path)),
+            };
+            labels.push(label);
+        }
+    }
+    let file_entries = folders.iter_mut().flat_map(|folder| &mut folder.files);
+    for (file_entry, label) in file_entries.zip(labels) {
+        file_entry.folder_label = label;
+    }
 }
 
 impl Folder {
+    pub fn new(path: PathBuf, files: Vec<FileEntry>) -> Self {
+        Self {
+            path,
+            files,
+            expanded: true,
+            to_be_deleted: false,
+            default_options: CSVFile::default(),
+            collapsed_groups: vec![],
+            order: None,
+        }
+    }
+
+    /// indices into `files` in the order they are listed
+    pub fn sorted_indices(&mut self, sort: FileSort) -> Vec<usize> {
+        match &self.order {
+            // sizes and modification times are only read when the mode changes
+            Some((cached, order)) if *cached == sort && order.len() == self.files.len() => {
+                order.clone()
+            }
+            _ => {
+                let order = self.sort_files(sort);
+                self.order = Some((sort, order.clone()));
+                order
+            }
+        }
+    }
+
+    fn sort_files(&self, sort: FileSort) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.files.len()).collect();
+        let filename = |idx: &usize| self.files[*idx].filename.as_str();
+        match sort {
+            FileSort::Name => order.sort_by_key(|idx| filename(idx).to_lowercase()),
+            FileSort::NameNatural => order.sort_by(|a, b| natural_cmp(filename(a), filename(b))),
+            FileSort::ModifiedTime | FileSort::Size => {
+                // files without metadata go last
+                let keys: Vec<Option<u128>> = self
+                    .files
+                    .iter()
+                    .map(|file_entry| {
+                        let metadata =
+                            std::fs::metadata(self.path.join(&file_entry.filename)).ok()?;
+                        match sort {
+                            FileSort::Size => Some(metadata.len() as u128),
+                            _ => metadata
+                                .modified()
+                                .ok()?
+                                .duration_since(std::time::UNIX_EPOCH)
+                                .ok()
+                                .map(|duration| duration.as_nanos()),
+                        }
+                    })
+                    .collect();
+                order.sort_by_key(|idx| (keys[*idx].is_none(), keys[*idx]));
+            }
+        }
+        order
+    }
+
     pub fn list_files_ui(
         &mut self,
         ui: &mut egui::Ui,
         search_phrase: &str,
-        error_log: &mut Vec<String>,
-    ) {
-        for file_entry in self.files.iter_mut() {
-            if !file_entry.should_be_listed(search_phrase, self.expanded) {
-                continue;
-            }
-
-            let file_label = file_entry
-                .get_file_label()
-                .truncate()
-                .ui(ui)
-                .on_hover_ui(|ui| {
-                    ui.label(&file_entry.preview);
-                });
-
-            if file_label.clicked() {
-                // lazily load the data
-                // TODO: if file was updated, it should be reloaded
-                file_entry.clicked(&self.path, error_log);
+        loader: &mut Loader,
+        selected_file: Option<usize>,
+        scroll_to_selected: bool,
+        sort: FileSort,
+    ) -> Option<(usize, bool)> {
+        // id of a file the user (de)activated, and its new state
+        let mut activated = None;
+        let order = self.sorted_indices(sort);
+        // ungrouped files come first, followed by the groups in alphabetical order
+        let mut groups: Vec<&str> = self
+            .files
+            .iter()
+            .filter_map(|file_entry| file_entry.group.as_deref())
+            .collect();
+        groups.sort_unstable();
+        groups.dedup();
+        let mut rows: Vec<(Option<String>, Vec<usize>)> = vec![(None, vec![])];
+        rows.extend(
+            groups
+                .into_iter()
+                .map(|group| (Some(group.to_owned()), vec![])),
+        );
+        for idx in order {
+            let group = &self.files[idx].group;
+            if let Some((_, members)) = rows.iter_mut().find(|(name, _)| name == group) {
+                members.push(idx);
+            }
+        }
+
+        for (group, members) in rows {
+            // the selected file is shown even if the filter does not match it
+            let is_listed = |file_entry: &FileEntry| {
+                (self.expanded && selected_file == Some(file_entry.id))
+                    || file_entry.should_be_listed(search_phrase, self.expanded)
             };
+            if let Some(group) = &group {
+                if !members.iter().any(|idx| is_listed(&self.files[*idx])) {
+                    continue;
+                }
+                let collapsed = self.collapsed_groups.contains(group);
+                let header = egui::RichText::new(format!(
+                    "{} {} ({})",
+                    if collapsed { "⏵" } else { "⏷" },
+                    group,
+                    members.len()
+                ))
+                .strong();
+                if ui
+                    .add(egui::Label::new(header).sense(egui::Sense::click()))
+                    .clicked()
+                {
+                    if collapsed {
+                        self.collapsed_groups.retain(|name| name != group);
+                    } else {
+                        self.collapsed_groups.push(group.clone());
+                    }
+                }
+                if collapsed {
+                    continue;
+                }
+            }
+            for idx in members {
+                if !is_listed(&self.files[idx]) {
+                    continue;
+                }
+                let file_entry = &mut self.files[idx];
+                activated = activated.or(file_entry_ui(
+                    ui,
+                    file_entry,
+                    &self.path,
+                    &self.default_options,
+                    loader,
+                    selected_file,
+                    scroll_to_selected,
+                ));
+            }
+        }
+        activated
+    }
+
+    /// files in collapsed groups are not listed in the tree
+    pub fn is_collapsed(&self, file_entry: &FileEntry) -> bool {
+        file_entry
+            .group
+            .as_ref()
+            .is_some_and(|group| self.collapsed_groups.contains(group))
+    }
+}
+
+/// row of a file in the tree, returns the file's id and whether it should
+/// become active if the user toggled that
+fn file_entry_ui(
+    ui: &mut egui::Ui,
+    file_entry: &mut FileEntry,
+    folder_path: &Path,
+    default_options: &CSVFile,
+    loader: &mut Loader,
+    selected_file: Option<usize>,
+    scroll_to_selected: bool,
+) -> Option<(usize, bool)> {
+    let mut activate_clicked = false;
+    let mut file_label = ui
+        .horizontal(|ui| {
+            if file_entry.group.is_some() {
+                ui.add_space(ui.spacing().indent);
+            }
+            if file_entry.is_loading() {
+                ui.spinner();
+            }
+            if file_entry.is_plotted() {
+                ui.toggle_value(&mut file_entry.visible, "👁")
+                    .on_hover_text("Show or hide in the plot");
+                activate_clicked = ui
+                    .selectable_label(file_entry.is_active(), "A")
+                    .on_hover_text(
+                        "Make active (or right-click the file), drag manipulations apply to active files",
+                    )
+                    .clicked();
+            }
+            let label = file_entry.get_file_label().truncate().ui(ui);
+            if let Some(count) = file_entry.get_point_count_text() {
+                ui.label(count);
+            }
+            label
+        })
+        .inner
+        .on_hover_ui(|ui| {
+            if let Some(hint) = file_entry.needs_config_hint() {
+                ui.colored_label(ui.visuals().warn_fg_color, hint);
+            }
+            if let Some(parse_stats) = file_entry.parse_stats_text() {
+                ui.weak(parse_stats);
+            }
+            preview_table_ui(ui, file_entry);
+        });
 
-            // toggle plotted or active
-            if file_label.secondary_clicked() {
-                file_entry.secondary_clicked()
+    // keyboard selection, or the file's line is hovered in the plot
+    if file_entry.hovered_in_plot {
+        file_label = file_label.highlight();
+    }
+    if selected_file == Some(file_entry.id) {
+        file_label = file_label.highlight();
+        if scroll_to_selected {
+            file_label.scroll_to_me(None);
+        }
+    }
+
+    if file_label.clicked() {
+        // lazily load the data
+        // TODO: if file was updated, it should be reloaded
+        file_entry.clicked(folder_path, default_options, loader);
+    };
+
+    // toggle active
+    if activate_clicked || (file_label.secondary_clicked() && file_entry.is_plotted()) {
+        return Some((file_entry.id, !file_entry.is_active()));
+    }
+    None
+}
+
+// rows of the file shown in the hover preview
+const PREVIEW_ROWS: usize = 10;
+
+/// the first rows of the file split into columns, with the columns read as
+/// x and y highlighted, or the raw lines if they can not be split
+fn preview_table_ui(ui: &mut egui::Ui, file_entry: &FileEntry) {
+    let options = &file_entry.data_file;
+    let rows = if options.field_pattern.is_empty() {
+        preview_fields(&file_entry.preview, options, PREVIEW_ROWS)
+    } else {
+        // lines matched by a pattern are not split at the delimiter
+        None
+    };
+    let Some(rows) = rows else {
+        ui.label(&file_entry.preview);
+        return;
+    };
+    let (xcol, ycol) = if options.parametric {
+        (Some(options.param_xcol), options.param_ycol)
+    } else if options.x_is_index {
+        (None, options.ycol)
+    } else {
+        (Some(options.xcol), options.ycol)
+    };
+    let highlight_color = ui.visuals().selection.stroke.color;
+    let highlight = |text: egui::RichText, col: usize| {
+        if xcol == Some(col) || ycol == col {
+            text.strong().color(highlight_color)
+        } else {
+            text
+        }
+    };
+    let num_cols = rows.iter().map(Vec::len).max().unwrap_or(0);
+    egui::Grid::new(("preview_table", file_entry.id))
+        .striped(true)
+        .show(ui, |ui| {
+            for col in 0..num_cols {
+                let axes = match (xcol == Some(col), ycol == col) {
+                    (true, true) => " (x, y)",
+                    (true, false) => " (x)",
+                    (false, true) => " (y)",
+                    (false, false) => "",
+                };
+                ui.label(highlight(
+                    egui::RichText::new(format!("{col}{axes}")).weak(),
+                    col,
+                ));
             }
+            ui.end_row();
+            for row in &rows {
+                for (col, field) in row.iter().enumerate() {
+                    ui.label(highlight(egui::RichText::new(field), col));
+                }
+                ui.end_row();
+            }
+        });
+}
+
+/// compare names chunk by chunk, runs of digits are compared by their value
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        match (a.is_empty(), b.is_empty()) {
+            (true, true) => return Ordering::Equal,
+            (true, false) => return Ordering::Less,
+            (false, true) => return Ordering::Greater,
+            _ => (),
+        }
+        let (chunk_a, rest_a) = split_chunk(a);
+        let (chunk_b, rest_b) = split_chunk(b);
+        let is_number = |chunk: &str| chunk.starts_with(|c: char| c.is_ascii_digit());
+        let ordering = if is_number(chunk_a) && is_number(chunk_b) {
+            // leading zeros do not change the value
+            let (num_a, num_b) = (
+                chunk_a.trim_start_matches('0'),
+                chunk_b.trim_start_matches('0'),
+            );
+            num_a.len().cmp(&num_b.len()).then_with(|| num_a.cmp(num_b))
+        } else {
+            chunk_a.to_lowercase().cmp(&chunk_b.to_lowercase())
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
         }
+        (a, b) = (rest_a, rest_b);
     }
 }
+
+/// leading run of either digits or non-digits, and the remainder
+fn split_chunk(s: &str) -> (&str, &str) {
+    let digits = s.starts_with(|c: char| c.is_ascii_digit());
+    let end = s
+        .find(|c: char| c.is_ascii_digit() != digits)
+        .unwrap_or(s.len());
+    s.split_at(end)
+}
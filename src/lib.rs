@@ -3,8 +3,15 @@ mod csvfile;
 mod errors;
 #[allow(unused, dead_code)]
 mod event;
+mod export;
 mod file_entry;
+mod fit;
 mod folder;
+mod loader;
+mod minimap;
+mod pdf;
 mod plot;
+mod transform;
 
 pub use app::App;
+pub use csvfile::{CSVFile, NonFinitePolicy, NumberFormat, ParseStats, ParseWarning};
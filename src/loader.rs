@@ -0,0 +1,124 @@
+use std::{
+    path::PathBuf,
+    sync::mpsc::{channel, Receiver, Sender},
+};
+
+use crate::csvfile::{CSVFile, ParseWarning};
+
+// parsing of CSV files happens on background threads, so that large
+// files do not freeze the UI
+pub struct LoadResult {
+    pub file_id: usize,
+    /// tells the result apart from those of earlier loads of the same file
+    pub generation: usize,
+    pub csvfile: Option<CSVFile>,
+    /// the file was read, but no data points were left after parsing
+    pub parsed_empty: bool,
+    pub errors: Vec<String>,
+}
+
+pub struct Loader {
+    sender: Sender<LoadResult>,
+    receiver: Receiver<LoadResult>,
+    pending: usize,
+    // loads that were cancelled, but whose threads are still running
+    cancelled: usize,
+    // loads started and finished since the loader was last idle
+    batch_total: usize,
+    batch_done: usize,
+    // generation of the last started load
+    generation: usize,
+    // wakes up the UI when a load finishes, so it does not have to poll
+    repaint: Option<egui::Context>,
+}
+
+impl Default for Loader {
+    fn default() -> Self {
+        let (sender, receiver) = channel();
+        Self {
+            sender,
+            receiver,
+            pending: 0,
+            cancelled: 0,
+            batch_total: 0,
+            batch_done: 0,
+            generation: 0,
+            repaint: None,
+        }
+    }
+}
+
+impl Loader {
+    /// the context to repaint when a background load finishes
+    pub fn set_repaint_context(&mut self, ctx: &egui::Context) {
+        if self.repaint.is_none() {
+            self.repaint = Some(ctx.clone());
+        }
+    }
+
+    /// start parsing `filepath`, the returned generation comes back with
+    /// the result, generations start at 1
+    pub fn load(&mut self, file_id: usize, filepath: PathBuf, options: CSVFile) -> usize {
+        let sender = self.sender.clone();
+        let repaint = self.repaint.clone();
+        self.pending += 1;
+        self.batch_total += 1;
+        self.generation += 1;
+        let generation = self.generation;
+        std::thread::spawn(move || {
+            let (csvfile, warnings) = CSVFile::parse(filepath.clone(), &options);
+            let parsed_empty = csvfile.is_none()
+                && !warnings
+                    .iter()
+                    .any(|warning| matches!(warning, ParseWarning::Unreadable(_)));
+            let errors = warnings
+                .iter()
+                .map(|warning| warning.log_message(&filepath))
+                .collect();
+            // if the loader was dropped in the meantime (e.g. a session
+            // was loaded), nobody is interested in the result anymore
+            let _ = sender.send(LoadResult {
+                file_id,
+                generation,
+                csvfile,
+                parsed_empty,
+                errors,
+            });
+            if let Some(ctx) = repaint {
+                ctx.request_repaint();
+            }
+        });
+        generation
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.pending > 0
+    }
+
+    /// results of all loads that finished since the last call
+    pub fn finished(&mut self) -> Vec<LoadResult> {
+        let results: Vec<LoadResult> = self.receiver.try_iter().collect();
+        self.pending = self.pending.saturating_sub(results.len());
+        // results of cancelled loads are counted first
+        let num_cancelled = results.len().min(self.cancelled);
+        self.cancelled -= num_cancelled;
+        self.batch_done += results.len() - num_cancelled;
+        if self.pending == 0 {
+            self.batch_total = 0;
+            self.batch_done = 0;
+        }
+        results
+    }
+
+    /// number of finished and started loads since the loader was last idle
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        (self.batch_total > 0).then_some((self.batch_done, self.batch_total))
+    }
+
+    /// forget about the running loads, the caller has to ignore their results
+    pub fn cancel(&mut self) {
+        self.cancelled = self.pending;
+        self.batch_total = 0;
+        self.batch_done = 0;
+    }
+}
@@ -1,8 +1,23 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
-use plotme::App;
+use std::path::{Path, PathBuf};
+
+use plotme::{App, CSVFile};
 
 fn main() -> eframe::Result {
+    // command line mode: plot data piped in, without opening a window
+    if std::env::args_os().any(|arg| arg == "--stdin") {
+        attach_console();
+        let args: Vec<String> = std::env::args_os()
+            .skip(1)
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        if let Err(msg) = plot_stdin(&args) {
+            eprintln!("{}", msg);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([800.0, 600.0])
@@ -12,6 +27,82 @@ fn main() -> eframe::Result {
     eframe::run_native(
         "PlotMe CSV File Plotter",
         options,
-        Box::new(|_cc| Ok(Box::new(App::with_search_phrase(".csv")))),
+        Box::new(|_cc| {
+            let mut app = App::with_search_phrase(".csv");
+            // a session file given as argument, e.g. when opened via a file association
+            if let Some(path) = std::env::args_os().nth(1).map(PathBuf::from) {
+                let loaded = if path.is_file() {
+                    app.load_state(Some(path))
+                } else {
+                    Err(format!("WARNING: session file {:?} not found.", path))
+                };
+                if let Err(msg) = loaded {
+                    app.errors.push(msg);
+                }
+            }
+            Ok(Box::new(app))
+        }),
     )
 }
+
+/// release builds on Windows have no console of their own, the messages of the
+/// command line mode go to the one of the calling shell
+#[cfg(windows)]
+fn attach_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    // fails if there is no such console or one is attached already, in both
+    // cases stderr stays as it is
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+/// `plotme --stdin --out <file> [--xcol N] [--ycol N] [--delimiter C]
+/// [--skip-header N]`, the plot is a PDF if the output file ends in `.pdf`
+/// and an SVG otherwise, parse warnings go to stderr
+fn plot_stdin(args: &[String]) -> Result<(), String> {
+    // an export should show all points, large input is not sampled
+    let mut options = CSVFile {
+        load_full: true,
+        ..Default::default()
+    };
+    let mut out = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or(format!("ERROR: missing value after {}.", arg))
+        };
+        let count = |value: &String| {
+            value
+                .parse::<usize>()
+                .map_err(|err| format!("ERROR: invalid value {:?} for {}: {}", value, arg, err))
+        };
+        match arg.as_str() {
+            "--stdin" => (),
+            "--out" => out = Some(PathBuf::from(value()?)),
+            "--xcol" => options.xcol = count(value()?)?,
+            "--ycol" => options.ycol = count(value()?)?,
+            "--skip-header" => options.skip_header = count(value()?)?,
+            "--delimiter" => {
+                options.delimiter = *value()?
+                    .as_bytes()
+                    .first()
+                    .ok_or("ERROR: empty delimiter.".to_string())?
+            }
+            _ => return Err(format!("ERROR: unknown argument {}.", arg)),
+        }
+    }
+    let out = out.ok_or("ERROR: no output file given, use --out <file>.".to_string())?;
+    let source = Path::new("stdin");
+    let (csvfile, warnings) =
+        CSVFile::parse_reader(std::io::stdin().lock(), source.into(), &options);
+    for warning in &warnings {
+        eprintln!("{}", warning.log_message(source));
+    }
+    let csvfile = csvfile.ok_or("ERROR: no data points read from stdin.".to_string())?;
+    App::default().export_data("stdin", csvfile, &out)
+}
@@ -0,0 +1,161 @@
+use egui::{Color32, Rect, Sense, Stroke};
+
+use crate::{export::widen_degenerate, fit::stats, plot::PlotDimensions, App};
+
+// points per file drawn in the minimap, more are not visible at its size
+const MINIMAP_POINTS: usize = 500;
+const MINIMAP_HEIGHT: f32 = 60.0;
+
+/// the full extent of the files drawn in the active tab, recomputed only
+/// when the drawn files, their transforms or colors change
+#[derive(Default)]
+pub struct Minimap {
+    key: Vec<(usize, String, Color32)>,
+    extent: Option<PlotDimensions>,
+    curves: Vec<(Color32, Vec<[f64; 2]>)>,
+}
+
+impl App {
+    fn update_minimap(&mut self) {
+        let tab = self.plot_tabs.active().id;
+        let drawn = || {
+            self.folders
+                .iter()
+                .flat_map(|folder| &folder.files)
+                .filter(move |file_entry| file_entry.is_drawn_in(tab))
+        };
+        let key: Vec<(usize, String, Color32)> = drawn()
+            .map(|file_entry| (file_entry.id, file_entry.transform_key(), file_entry.color))
+            .collect();
+        if key == self.minimap.key {
+            return;
+        }
+        let mut extent: Option<PlotDimensions> = None;
+        let mut curves = vec![];
+        for file_entry in drawn() {
+            let data = file_entry.transformed_data();
+            if let Some(stats) = stats(&data) {
+                let dims = extent.get_or_insert(PlotDimensions {
+                    x0: f32::INFINITY,
+                    x1: f32::NEG_INFINITY,
+                    y0: f32::INFINITY,
+                    y1: f32::NEG_INFINITY,
+                });
+                dims.x0 = dims.x0.min(stats.xmin as f32);
+                dims.x1 = dims.x1.max(stats.xmax as f32);
+                dims.y0 = dims.y0.min(stats.ymin as f32);
+                dims.y1 = dims.y1.max(stats.ymax as f32);
+            }
+            let stride = (data.len() / MINIMAP_POINTS).max(1);
+            let points = data
+                .into_iter()
+                .step_by(stride)
+                .filter(|[x, y]| x.is_finite() && y.is_finite())
+                .collect();
+            curves.push((file_entry.color, points));
+        }
+        self.minimap.key = key;
+        // a single point has no extent to navigate, a flat line is drawn
+        // in the middle
+        self.minimap.extent = extent.filter(|dims| dims.xspan() > 0.0).map(|mut dims| {
+            let y = widen_degenerate(dims.y0..dims.y1);
+            (dims.y0, dims.y1) = (y.start, y.end);
+            dims
+        });
+        self.minimap.curves = curves;
+    }
+
+    /// overview of all data drawn in the active tab, dragging the view
+    /// rectangle pans the plot, clicking centers the view on the cursor
+    pub fn minimap_ui(&mut self, ui: &mut egui::Ui) {
+        self.update_minimap();
+        let (rect, response) = ui.allocate_exact_size(
+            egui::vec2(ui.available_width(), MINIMAP_HEIGHT),
+            Sense::click_and_drag(),
+        );
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 2.0, ui.visuals().extreme_bg_color);
+        let Some(extent) = &self.minimap.extent else {
+            return;
+        };
+        let to_screen = |x: f64, y: f64| {
+            egui::pos2(
+                rect.left() + (x as f32 - extent.x0) / extent.xspan() * rect.width(),
+                rect.bottom() - (y as f32 - extent.y0) / extent.yspan() * rect.height(),
+            )
+        };
+        for (color, points) in &self.minimap.curves {
+            let line = points.iter().map(|[x, y]| to_screen(*x, *y)).collect();
+            painter.add(egui::Shape::line(line, Stroke::new(1.0, *color)));
+        }
+        let view = &self.plot_tabs.active().dims;
+        let view_rect = Rect::from_two_pos(
+            to_screen(view.x0 as f64, view.y0 as f64),
+            to_screen(view.x1 as f64, view.y1 as f64),
+        );
+        painter.rect_stroke(view_rect, 0.0, ui.visuals().selection.stroke);
+
+        // shift of the view in plot units
+        let shift = if response.dragged() {
+            let delta = response.drag_delta();
+            Some((
+                delta.x / rect.width() * extent.xspan(),
+                -delta.y / rect.height() * extent.yspan(),
+            ))
+        } else if response.clicked() {
+            response.interact_pointer_pos().map(|pos| {
+                let delta = pos - view_rect.center();
+                (
+                    delta.x / rect.width() * extent.xspan(),
+                    -delta.y / rect.height() * extent.yspan(),
+                )
+            })
+        } else {
+            None
+        };
+        if let Some((dx, dy)) = shift {
+            self.pending_bounds = Some(egui_plot::PlotBounds::from_min_max(
+                [(view.x0 + dx) as f64, (view.y0 + dy) as f64],
+                [(view.x1 + dx) as f64, (view.y1 + dy) as f64],
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        app::ManipulationDefaults,
+        file_entry::{get_file_entries, FileEntryState},
+        folder::Folder,
+    };
+
+    fn app_with(name: &str, data: Vec<[f64; 2]>) -> App {
+        let dir = std::env::temp_dir().join(name);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("data.csv"), "").unwrap();
+        let mut file_entry =
+            get_file_entries(&dir, &mut 0, &ManipulationDefaults::default(), &mut vec![])
+                .pop()
+                .unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+        file_entry.data_file.data = data;
+        file_entry.restore_state(FileEntryState::Plotted);
+        file_entry.tab = Some(0);
+        let mut app = App::default();
+        app.folders = vec![Folder::new("data".into(), vec![file_entry])];
+        app
+    }
+
+    #[test]
+    fn flat_line_has_an_extent() {
+        let mut app = app_with("plotme_minimap_flat", vec![[0.0, 2.0], [1.0, 2.0]]);
+        app.update_minimap();
+        let extent = app.minimap.extent.as_ref().unwrap();
+        assert!(extent.y0 < 2.0 && extent.y1 > 2.0);
+        let mut app = app_with("plotme_minimap_point", vec![[1.0, 2.0]]);
+        app.update_minimap();
+        assert!(app.minimap.extent.is_none());
+    }
+}
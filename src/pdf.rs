@@ -0,0 +1,554 @@
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use ab_glyph::{Font, FontArc};
+use plotters_backend::{
+    text_anchor::{HPos, VPos},
+    BackendColor, BackendCoord, BackendStyle, BackendTextStyle, DrawingBackend, DrawingErrorKind,
+    FontFamily, FontStyle, FontTransform,
+};
+
+// a single page PDF with the chart as vector graphics, texts are set in the
+// fonts of the UI, which are embedded, so that any character they cover
+// shows up like on screen
+
+// plotters font sizes are larger than the resulting glyphs, the SVG backend
+// divides them by the same factor
+const FONT_SIZE_FACTOR: f64 = 1.24;
+
+// control point distance of the bezier curves that approximate a quarter circle
+const CIRCLE_KAPPA: f64 = 0.5523;
+
+// slant of italic and oblique text, which the fonts have no variant for
+const OBLIQUE_SKEW: f64 = 0.2;
+
+// outline width of bold text, in em
+const BOLD_STROKE: f64 = 0.03;
+
+/// a TrueType font of the UI, embedded as composite font whose character
+/// codes are the glyph ids
+struct EmbeddedFont {
+    name: &'static str,
+    data: Vec<u8>,
+    font: FontArc,
+    // the glyphs in use and the characters they show, for the widths and the
+    // text extraction of the viewer
+    used: BTreeMap<u16, char>,
+}
+
+impl EmbeddedFont {
+    fn new(name: &'static str) -> Self {
+        let definitions = egui::FontDefinitions::default();
+        let data = definitions.font_data[name].font.to_vec();
+        let font = FontArc::try_from_vec(data.clone()).expect("the egui fonts are valid");
+        Self {
+            name,
+            data,
+            font,
+            used: BTreeMap::new(),
+        }
+    }
+
+    fn units_per_em(&self) -> f64 {
+        self.font.units_per_em().unwrap_or(1000.0) as f64
+    }
+
+    /// advance of the glyph in 1/1000 em
+    fn glyph_width(&self, glyph: ab_glyph::GlyphId) -> f64 {
+        self.font.h_advance_unscaled(glyph) as f64 * 1000.0 / self.units_per_em()
+    }
+
+    /// width of `text` in em
+    fn text_width(&self, text: &str) -> f64 {
+        let width: f64 = text
+            .chars()
+            .map(|c| self.glyph_width(self.font.glyph_id(c)))
+            .sum();
+        width / 1000.0
+    }
+
+    /// height above and below the baseline in em, the descent is negative
+    fn ascent_descent(&self) -> (f64, f64) {
+        let units = self.units_per_em();
+        (
+            self.font.ascent_unscaled() as f64 / units,
+            self.font.descent_unscaled() as f64 / units,
+        )
+    }
+
+    /// `text` as hex string of glyph ids, the glyphs are remembered
+    fn encode(&mut self, text: &str) -> String {
+        let mut hex = String::with_capacity(text.len() * 4 + 2);
+        hex.push('<');
+        for c in text.chars() {
+            let glyph = self.font.glyph_id(c);
+            self.used.entry(glyph.0).or_insert(c);
+            hex.push_str(&format!("{:04X}", glyph.0));
+        }
+        hex.push('>');
+        hex
+    }
+
+    /// the objects of the font, numbered from `first`, the first one is the
+    /// font dictionary the page refers to
+    fn objects(&self, first: usize) -> Vec<Vec<u8>> {
+        let (ascent, descent) = self.ascent_descent();
+        let widths: String = self
+            .used
+            .keys()
+            .map(|glyph| {
+                let width = self.glyph_width(ab_glyph::GlyphId(*glyph));
+                format!("{} [{:.0}] ", glyph, width)
+            })
+            .collect();
+        let to_unicode: String = self
+            .used
+            .iter()
+            .map(|(glyph, c)| {
+                let utf16: String = c
+                    .encode_utf16(&mut [0; 2])
+                    .iter()
+                    .map(|unit| format!("{:04X}", unit))
+                    .collect();
+                format!("<{:04X}> <{}>\n", glyph, utf16)
+            })
+            .collect();
+        // a viewer reads at most 100 mappings per block
+        let mut cmap = String::new();
+        for chunk in to_unicode.lines().collect::<Vec<_>>().chunks(100) {
+            cmap.push_str(&format!(
+                "{} beginbfchar\n{}\nendbfchar\n",
+                chunk.len(),
+                chunk.join("\n")
+            ));
+        }
+        let cmap = format!(
+            "/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+             /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+             /CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n\
+             1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n\
+             {}endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend",
+            cmap
+        );
+        vec![
+            format!(
+                "<< /Type /Font /Subtype /Type0 /BaseFont /{} /Encoding /Identity-H \
+                 /DescendantFonts [{} 0 R] /ToUnicode {} 0 R >>",
+                self.name,
+                first + 1,
+                first + 4
+            )
+            .into_bytes(),
+            format!(
+                "<< /Type /Font /Subtype /CIDFontType2 /BaseFont /{} \
+                 /CIDSystemInfo << /Registry (Adobe) /Ordering (Identity) /Supplement 0 >> \
+                 /FontDescriptor {} 0 R /CIDToGIDMap /Identity /W [{}] >>",
+                self.name,
+                first + 2,
+                widths
+            )
+            .into_bytes(),
+            format!(
+                "<< /Type /FontDescriptor /FontName /{} /Flags 32 \
+                 /FontBBox [-500 {:.0} 1500 {:.0}] /ItalicAngle 0 /Ascent {:.0} \
+                 /Descent {:.0} /CapHeight {:.0} /StemV 80 /FontFile2 {} 0 R >>",
+                self.name,
+                descent * 1000.0,
+                ascent * 1000.0,
+                ascent * 1000.0,
+                descent * 1000.0,
+                ascent * 700.0,
+                first + 3
+            )
+            .into_bytes(),
+            stream_object(&format!("/Length1 {}", self.data.len()), self.data.clone()),
+            stream_object("", cmap.into_bytes()),
+        ]
+    }
+}
+
+/// a stream object with `entries` in its dictionary next to the length
+fn stream_object(entries: &str, data: Vec<u8>) -> Vec<u8> {
+    [
+        format!("<< /Length {} {}>>\nstream\n", data.len(), entries).into_bytes(),
+        data,
+        b"\nendstream".to_vec(),
+    ]
+    .concat()
+}
+
+pub struct PDFBackend {
+    path: PathBuf,
+    size: (u32, u32),
+    page_size: (f64, f64),
+    content: Vec<u8>,
+    // opacities in use, `/GS<index>` refers to them in the content stream
+    alphas: Vec<String>,
+    // `/F<index>` in the content stream, see `font_index`
+    fonts: [EmbeddedFont; 2],
+}
+
+impl PDFBackend {
+    /// a chart of `size` drawn onto a page of `page_size` points, scaled
+    /// to fit and centered
+    pub fn new(path: &Path, size: (u32, u32), page_size: (f64, f64)) -> Self {
+        Self {
+            path: path.to_owned(),
+            size,
+            page_size,
+            content: Vec::new(),
+            alphas: Vec::new(),
+            fonts: [EmbeddedFont::new("Ubuntu-Light"), EmbeddedFont::new("Hack")],
+        }
+    }
+
+    fn graphics_state(&mut self, alpha: f64) -> usize {
+        let alpha = format!("{:.3}", alpha.clamp(0.0, 1.0));
+        match self.alphas.iter().position(|known| *known == alpha) {
+            Some(index) => index,
+            None => {
+                self.alphas.push(alpha);
+                self.alphas.len() - 1
+            }
+        }
+    }
+
+    /// paint the path built by `path_ops`, every shape gets its own graphics
+    /// state, so that colors and widths do not leak into the next one
+    fn paint(&mut self, color: BackendColor, stroke_width: Option<u32>, path_ops: &str) {
+        if color.alpha == 0.0 {
+            return;
+        }
+        let gs = self.graphics_state(color.alpha);
+        let (r, g, b) = color.rgb;
+        let rgb = format!(
+            "{:.3} {:.3} {:.3}",
+            r as f64 / 255.0,
+            g as f64 / 255.0,
+            b as f64 / 255.0
+        );
+        let paint = match stroke_width {
+            Some(width) => format!("{} RG {} w 1 j {}S", rgb, width, path_ops),
+            None => format!("{} rg {}f", rgb, path_ops),
+        };
+        self.content
+            .extend(format!("q /GS{} gs {} Q\n", gs, paint).bytes());
+    }
+
+    fn document(&self) -> Vec<u8> {
+        let (width, height) = (self.size.0 as f64, self.size.1 as f64);
+        let (page_width, page_height) = self.page_size;
+        let scale = (page_width / width).min(page_height / height);
+        let dx = (page_width - width * scale) / 2.0;
+        let dy = (page_height - height * scale) / 2.0;
+        // plotters counts y downwards from the top
+        let mut stream = format!(
+            "{:.4} 0 0 {:.4} {:.2} {:.2} cm\n",
+            scale,
+            -scale,
+            dx,
+            page_height - dy
+        )
+        .into_bytes();
+        stream.extend(&self.content);
+
+        let graphics_states: String = self
+            .alphas
+            .iter()
+            .enumerate()
+            .map(|(i, alpha)| format!("/GS{} << /ca {} /CA {} >> ", i, alpha, alpha))
+            .collect();
+        // the font objects follow the content stream
+        let mut font_objects = vec![];
+        let mut fonts = String::new();
+        for (i, font) in self.fonts.iter().enumerate() {
+            let first = 5 + font_objects.len();
+            fonts.push_str(&format!("/F{} {} 0 R ", i, first));
+            font_objects.extend(font.objects(first));
+        }
+        let mut objects: Vec<Vec<u8>> = vec![
+            b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+            b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {:.2} {:.2}] \
+                 /Resources << /Font << {}>> /ExtGState << {}>> >> /Contents 4 0 R >>",
+                page_width, page_height, fonts, graphics_states
+            )
+            .into_bytes(),
+            stream_object("", stream),
+        ];
+        objects.extend(font_objects);
+
+        let mut pdf = b"%PDF-1.4\n".to_vec();
+        let mut offsets = vec![];
+        for (i, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.extend(format!("{} 0 obj\n", i + 1).bytes());
+            pdf.extend(object);
+            pdf.extend(b"\nendobj\n");
+        }
+        let xref = pdf.len();
+        pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).bytes());
+        for offset in offsets {
+            pdf.extend(format!("{:010} 00000 n \n", offset).bytes());
+        }
+        pdf.extend(
+            format!(
+                "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+                objects.len() + 1,
+                xref
+            )
+            .bytes(),
+        );
+        pdf
+    }
+}
+
+impl DrawingBackend for PDFBackend {
+    type ErrorType = std::io::Error;
+
+    fn get_size(&self) -> (u32, u32) {
+        self.size
+    }
+
+    fn ensure_prepared(&mut self) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        Ok(())
+    }
+
+    fn present(&mut self) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        std::fs::File::create(&self.path)
+            .and_then(|mut file| file.write_all(&self.document()))
+            .map_err(DrawingErrorKind::DrawingError)
+    }
+
+    fn draw_pixel(
+        &mut self,
+        (x, y): BackendCoord,
+        color: BackendColor,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.paint(color, None, &format!("{} {} 1 1 re ", x, y));
+        Ok(())
+    }
+
+    fn draw_line<S: BackendStyle>(
+        &mut self,
+        from: BackendCoord,
+        to: BackendCoord,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        self.draw_path([from, to], style)
+    }
+
+    fn draw_rect<S: BackendStyle>(
+        &mut self,
+        (x0, y0): BackendCoord,
+        (x1, y1): BackendCoord,
+        style: &S,
+        fill: bool,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let rect = format!("{} {} {} {} re ", x0, y0, x1 - x0, y1 - y0);
+        let stroke_width = (!fill).then(|| style.stroke_width());
+        self.paint(style.color(), stroke_width, &rect);
+        Ok(())
+    }
+
+    fn draw_path<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        path: I,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let path_ops = polyline(path);
+        if !path_ops.is_empty() {
+            self.paint(style.color(), Some(style.stroke_width()), &path_ops);
+        }
+        Ok(())
+    }
+
+    fn draw_circle<S: BackendStyle>(
+        &mut self,
+        (x, y): BackendCoord,
+        radius: u32,
+        style: &S,
+        fill: bool,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let (x, y, r) = (x as f64, y as f64, radius as f64);
+        let k = r * CIRCLE_KAPPA;
+        let mut circle = format!("{} {} m ", x + r, y);
+        for [c1, c2, end] in [
+            [(x + r, y + k), (x + k, y + r), (x, y + r)],
+            [(x - k, y + r), (x - r, y + k), (x - r, y)],
+            [(x - r, y - k), (x - k, y - r), (x, y - r)],
+            [(x + k, y - r), (x + r, y - k), (x + r, y)],
+        ] {
+            circle.push_str(&format!(
+                "{:.2} {:.2} {:.2} {:.2} {:.2} {:.2} c ",
+                c1.0, c1.1, c2.0, c2.1, end.0, end.1
+            ));
+        }
+        circle.push_str("h ");
+        let stroke_width = (!fill).then(|| style.stroke_width());
+        self.paint(style.color(), stroke_width, &circle);
+        Ok(())
+    }
+
+    fn fill_polygon<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(
+        &mut self,
+        vert: I,
+        style: &S,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let path_ops = polyline(vert);
+        if !path_ops.is_empty() {
+            self.paint(style.color(), None, &format!("{}h ", path_ops));
+        }
+        Ok(())
+    }
+
+    fn draw_text<TStyle: BackendTextStyle>(
+        &mut self,
+        text: &str,
+        style: &TStyle,
+        (x, y): BackendCoord,
+    ) -> Result<(), DrawingErrorKind<Self::ErrorType>> {
+        let color = style.color();
+        if color.alpha == 0.0 {
+            return Ok(());
+        }
+        let font_style = style.style();
+        let font = font_index(&style.family());
+        let size = style.size() / FONT_SIZE_FACTOR;
+        let (ascent, descent) = self.fonts[font].ascent_descent();
+        // shift of the baseline start against the anchor, along and across
+        // the direction of the text, like the SVG backend places it
+        let along = -self.fonts[font].text_width(text)
+            * size
+            * match style.anchor().h_pos {
+                HPos::Left => 0.0,
+                HPos::Center => 0.5,
+                HPos::Right => 1.0,
+            };
+        let across = size
+            * match style.anchor().v_pos {
+                VPos::Top => ascent,
+                VPos::Center => (ascent + descent) / 2.0,
+                VPos::Bottom => descent,
+            };
+        // clockwise rotation, as y points downwards
+        let (cos, sin) = match style.transform() {
+            FontTransform::None => (1.0, 0.0),
+            FontTransform::Rotate90 => (0.0, 1.0),
+            FontTransform::Rotate180 => (-1.0, 0.0),
+            FontTransform::Rotate270 => (0.0, -1.0),
+        };
+        let x = x as f64 + along * cos - across * sin;
+        let y = y as f64 + along * sin + across * cos;
+
+        let skew = match font_style {
+            FontStyle::Italic | FontStyle::Oblique => OBLIQUE_SKEW,
+            _ => 0.0,
+        };
+        // bold text is outlined in addition to being filled
+        let render_mode = match font_style {
+            FontStyle::Bold => format!("2 Tr {:.3} w", BOLD_STROKE * size),
+            _ => "0 Tr".to_string(),
+        };
+
+        let gs = self.graphics_state(color.alpha);
+        let (r, g, b) = color.rgb;
+        let rgb = format!(
+            "{:.3} {:.3} {:.3}",
+            r as f64 / 255.0,
+            g as f64 / 255.0,
+            b as f64 / 255.0
+        );
+        let glyphs = self.fonts[font].encode(text);
+        // the text matrix flips the glyphs upright again, the slant leans
+        // them along the direction of the text
+        let ops = format!(
+            "q /GS{} gs {} rg {} RG BT /F{} {:.2} Tf {} {:.3} {:.3} {:.3} {:.3} {:.2} {:.2} Tm {} Tj ET Q\n",
+            gs,
+            rgb,
+            rgb,
+            font,
+            size,
+            render_mode,
+            cos,
+            sin,
+            sin + skew * cos,
+            skew * sin - cos,
+            x,
+            y,
+            glyphs
+        );
+        self.content.extend(ops.bytes());
+        Ok(())
+    }
+
+    fn estimate_text_size<TStyle: BackendTextStyle>(
+        &self,
+        text: &str,
+        style: &TStyle,
+    ) -> Result<(u32, u32), DrawingErrorKind<Self::ErrorType>> {
+        let font = font_index(&style.family());
+        let size = style.size() / FONT_SIZE_FACTOR;
+        Ok((
+            (self.fonts[font].text_width(text) * size).ceil() as u32,
+            style.size().ceil() as u32,
+        ))
+    }
+}
+
+/// index into `PDFBackend::fonts`, bold and italic text is drawn with the
+/// regular fonts
+fn font_index(family: &FontFamily) -> usize {
+    match family {
+        FontFamily::Monospace => 1,
+        _ => 0,
+    }
+}
+
+/// `moveto` and `lineto` operators through the points, empty without points
+fn polyline<I: IntoIterator<Item = BackendCoord>>(points: I) -> String {
+    let mut ops = String::new();
+    for (i, (x, y)) in points.into_iter().enumerate() {
+        let op = if i == 0 { "m" } else { "l" };
+        ops.push_str(&format!("{} {} {} ", x, y, op));
+    }
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use plotters::prelude::*;
+
+    #[test]
+    fn document_with_unicode_text_can_be_parsed() {
+        let path = std::env::temp_dir().join(format!("plotme-test-{}.pdf", std::process::id()));
+        {
+            let root = PDFBackend::new(&path, (400, 300), (595.0, 842.0)).into_drawing_area();
+            root.fill(&WHITE).unwrap();
+            root.draw(&PathElement::new(vec![(10, 10), (390, 290)], BLACK))
+                .unwrap();
+            let style = ("sans-serif", 20.0).into_font().color(&BLACK);
+            root.draw(&Text::new("2π · 5 µm (Ω)", (20, 150), &style))
+                .unwrap();
+            root.present().unwrap();
+        }
+        let document = lopdf::Document::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let pages = document.get_pages();
+        assert_eq!(pages.len(), 1);
+        let text = document.extract_text(&[1]).unwrap();
+        assert!(text.contains("2π · 5 µm (Ω)"), "extracted {:?}", text);
+    }
+
+    #[test]
+    fn text_width_uses_the_font() {
+        let font = EmbeddedFont::new("Ubuntu-Light");
+        assert!(font.text_width("WWW") > font.text_width("iii"));
+        assert!(font.text_width("π") > 0.0);
+    }
+}
@@ -1,9 +1,15 @@
 use egui::{epaint::Hsva, Color32, Id};
 use serde::{Deserialize, Serialize};
 
-use crate::App;
+use crate::{
+    export::ExportStyle,
+    file_entry::{FileEntry, OffsetMode, RenderMode},
+    fit::{average, AverageRange},
+    folder::Folder,
+    App,
+};
 
-#[derive(Serialize, Deserialize, Default)]
+#[derive(Serialize, Deserialize, Default, Clone, Copy)]
 pub struct PlotDimensions {
     pub x0: f32,
     pub x1: f32,
@@ -11,6 +17,653 @@ pub struct PlotDimensions {
     pub y1: f32,
 }
 
+/// an independent plot with its own view, files are assigned to it by id
+#[derive(Serialize, Deserialize)]
+pub struct PlotTab {
+    pub id: usize,
+    pub name: String,
+    pub dims: PlotDimensions,
+}
+
+/// the plot tabs, there is always at least one
+#[derive(Serialize, Deserialize)]
+pub struct PlotTabs {
+    tabs: Vec<PlotTab>,
+    active: usize,
+}
+
+impl Default for PlotTabs {
+    fn default() -> Self {
+        Self {
+            tabs: vec![PlotTab {
+                id: 0,
+                name: "Plot 1".to_string(),
+                dims: PlotDimensions::default(),
+            }],
+            active: 0,
+        }
+    }
+}
+
+impl PlotTabs {
+    pub fn all(&self) -> &[PlotTab] {
+        &self.tabs
+    }
+    pub fn active(&self) -> &PlotTab {
+        &self.tabs[self.active.min(self.tabs.len() - 1)]
+    }
+    pub fn active_mut(&mut self) -> &mut PlotTab {
+        let idx = self.active.min(self.tabs.len() - 1);
+        &mut self.tabs[idx]
+    }
+    pub fn contains(&self, id: usize) -> bool {
+        self.tabs.iter().any(|tab| tab.id == id)
+    }
+    fn add(&mut self) {
+        let id = self.tabs.iter().map(|tab| tab.id + 1).max().unwrap_or(0);
+        self.tabs.push(PlotTab {
+            id,
+            name: format!("Plot {}", self.tabs.len() + 1),
+            dims: PlotDimensions::default(),
+        });
+        self.active = self.tabs.len() - 1;
+    }
+    /// tab bar, right-click a tab to rename or close it
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        if self.tabs.is_empty() {
+            *self = PlotTabs::default();
+        }
+        let mut closed = None;
+        let can_close = self.tabs.len() > 1;
+        ui.horizontal(|ui| {
+            for (idx, tab) in self.tabs.iter_mut().enumerate() {
+                ui.selectable_value(&mut self.active, idx, &tab.name)
+                    .context_menu(|ui| {
+                        ui.text_edit_singleline(&mut tab.name);
+                        if ui
+                            .add_enabled(can_close, egui::Button::new("Close Tab"))
+                            .clicked()
+                        {
+                            closed = Some(idx);
+                            ui.close_menu();
+                        }
+                    });
+            }
+            if ui.small_button("+").on_hover_text("New plot tab").clicked() {
+                self.add();
+            }
+        });
+        if let Some(idx) = closed {
+            // files of the closed tab move to the active one
+            self.tabs.remove(idx);
+            if self.active >= idx && self.active > 0 {
+                self.active -= 1;
+            }
+        }
+    }
+}
+
+/// point-wise mean of several files, computed once by "Average Plotted"
+#[derive(Serialize, Deserialize)]
+pub struct AverageCurve {
+    pub name: String,
+    /// id of the plot tab it is drawn in
+    pub tab: usize,
+    pub color: Color32,
+    /// `[x, mean, standard deviation]`
+    pub points: Vec<[f64; 3]>,
+    /// shade the area of ± one standard deviation around the mean
+    pub show_band: bool,
+}
+
+impl AverageCurve {
+    pub fn mean(&self) -> Vec<[f64; 2]> {
+        self.points.iter().map(|[x, y, _]| [*x, *y]).collect()
+    }
+    /// corners of the quadrilaterals between two grid points that make up
+    /// the ± std band, which is generally not convex as a whole
+    pub fn band_quads(&self) -> impl Iterator<Item = [[f64; 2]; 4]> + '_ {
+        self.points.windows(2).map(|pair| {
+            let ([xa, ya, sa], [xb, yb, sb]) = (pair[0], pair[1]);
+            [[xa, ya - sa], [xb, yb - sb], [xb, yb + sb], [xa, ya + sa]]
+        })
+    }
+}
+
+/// the plot-level configuration copied by "Copy Plot Settings"
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PlotSettings {
+    pub options: PlotOptions,
+    pub bounds: PlotDimensions,
+    pub export_style: ExportStyle,
+    pub color_cycle: ColorCycle,
+}
+
+/// free text placed at plot coordinates
+#[derive(Serialize, Deserialize)]
+pub struct TextAnnotation {
+    pub x: f64,
+    pub y: f64,
+    pub text: String,
+    pub color: Color32,
+    pub font_size: f32,
+    /// id of the plot tab it is drawn in
+    pub tab: usize,
+}
+
+/// plot-level settings, shared by the interactive plot and the export
+#[derive(Serialize, Deserialize, Clone)]
+pub struct PlotOptions {
+    /// caption of exported figures, no caption is drawn if it is empty
+    pub title: String,
+    pub title_font_size: f64,
+    pub title_font_family: String,
+    /// custom ticks as parsed by `parse_ticks`, automatic ticks are used if empty
+    #[serde(default)]
+    pub x_ticks: String,
+    #[serde(default)]
+    pub y_ticks: String,
+    #[serde(default)]
+    pub x_format: TickFormat,
+    #[serde(default)]
+    pub y_format: TickFormat,
+    /// draw the axis in the opposite direction, e.g. for wavenumbers
+    #[serde(default)]
+    pub x_reversed: bool,
+    #[serde(default)]
+    pub y_reversed: bool,
+    /// overview of the full data extent above the plot
+    #[serde(default)]
+    pub show_minimap: bool,
+    #[serde(default)]
+    pub grid: GridOptions,
+    /// mark the x-position of the pointer in all panels sharing the x-axis
+    #[serde(default)]
+    pub link_cursor: bool,
+    /// show the index, and the file row if it was kept, of the point of the
+    /// hovered line closest to the pointer
+    #[serde(default)]
+    pub show_point_index: bool,
+}
+
+impl Default for PlotOptions {
+    fn default() -> Self {
+        Self {
+            title: String::new(),
+            title_font_size: 20.0,
+            title_font_family: "sans-serif".to_string(),
+            x_ticks: String::new(),
+            y_ticks: String::new(),
+            x_format: TickFormat::default(),
+            y_format: TickFormat::default(),
+            x_reversed: false,
+            y_reversed: false,
+            show_minimap: false,
+            grid: GridOptions::default(),
+            link_cursor: false,
+            show_point_index: false,
+        }
+    }
+}
+
+/// x-position of the pointer over one of the plot panels, the panels that
+/// share the x-axis mark it with a vertical line
+#[derive(Default, Clone, Copy)]
+pub struct SharedCursor {
+    // id of the hovered panel and the x-value of the pointer
+    hovered: Option<(usize, f64)>,
+}
+
+impl SharedCursor {
+    pub fn hover(&mut self, panel: usize, x: f64) {
+        self.hovered = Some((panel, x));
+    }
+    /// the pointer left `panel`, a cursor over another panel is kept
+    pub fn leave(&mut self, panel: usize) {
+        if self.hovered.is_some_and(|(hovered, _)| hovered == panel) {
+            self.hovered = None;
+        }
+    }
+    pub fn x(&self) -> Option<f64> {
+        self.hovered.map(|(_, x)| x)
+    }
+}
+
+/// grid lines of the plot on screen and of the export
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct GridOptions {
+    pub show: bool,
+    /// distance of the grid lines, automatic if 0, custom ticks take precedence
+    pub x_spacing: f64,
+    pub y_spacing: f64,
+    /// color of the major grid lines of the export, minor lines use half of
+    /// its opacity, egui_plot draws its grid in the text color
+    pub color: Color32,
+}
+
+impl Default for GridOptions {
+    fn default() -> Self {
+        Self {
+            show: true,
+            x_spacing: 0.0,
+            y_spacing: 0.0,
+            color: Color32::from_black_alpha(51),
+        }
+    }
+}
+
+impl GridOptions {
+    fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.show, "Show grid");
+        ui.add_enabled_ui(self.show, |ui| {
+            egui::Grid::new("grid_spacing")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    for (axis, spacing) in [("x", &mut self.x_spacing), ("y", &mut self.y_spacing)]
+                    {
+                        ui.label(format!("{axis}-spacing"));
+                        ui.add(
+                            egui::DragValue::new(spacing)
+                                .range(0.0..=f64::MAX)
+                                .speed(0.1),
+                        )
+                        .on_hover_text("0 for automatic spacing");
+                        ui.end_row();
+                    }
+                });
+            ui.horizontal(|ui| {
+                ui.label("Color");
+                egui::color_picker::color_edit_button_srgba(
+                    ui,
+                    &mut self.color,
+                    egui::color_picker::Alpha::BlendOrAdditive,
+                );
+            })
+            .response
+            .on_hover_text("Only the export uses the color, the plot on screen uses the theme");
+        });
+    }
+}
+
+/// values of the grid lines every `spacing` within `low..=high`, empty for
+/// automatic spacing
+pub fn spaced_ticks(spacing: f64, low: f64, high: f64) -> Vec<f64> {
+    // too many lines would only make a gray area
+    const MAX_LINES: f64 = 1000.0;
+    if spacing <= 0.0
+        || !spacing.is_finite()
+        || !low.is_finite()
+        || !high.is_finite()
+        || (high - low) / spacing > MAX_LINES
+    {
+        return vec![];
+    }
+    let first = (low / spacing).ceil() as i64;
+    let last = (high / spacing).floor() as i64;
+    (first..=last).map(|i| i as f64 * spacing).collect()
+}
+
+/// number format of the tick labels of an axis
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TickFormat {
+    /// the format of the renderer
+    #[default]
+    Auto,
+    /// fixed number of decimals
+    Fixed(usize),
+    Scientific,
+    /// SI prefix like `1.5k`
+    SiPrefix,
+}
+
+impl TickFormat {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Auto => "Auto",
+            Self::Fixed(_) => "Fixed",
+            Self::Scientific => "Scientific",
+            Self::SiPrefix => "SI prefix",
+        }
+    }
+    /// the label of `value`, `None` leaves it to the renderer
+    pub fn format(&self, value: f64) -> Option<String> {
+        match self {
+            Self::Auto => None,
+            Self::Fixed(decimals) => Some(format!("{:.*}", decimals, value)),
+            Self::Scientific => Some(format!("{:.2e}", value)),
+            Self::SiPrefix => Some(si_prefixed(value)),
+        }
+    }
+    fn ui(&mut self, ui: &mut egui::Ui, id: &str) {
+        let decimals = match self {
+            Self::Fixed(decimals) => *decimals,
+            _ => 2,
+        };
+        egui::ComboBox::from_id_source(id)
+            .selected_text(self.label())
+            .show_ui(ui, |ui| {
+                for format in [
+                    Self::Auto,
+                    Self::Fixed(decimals),
+                    Self::Scientific,
+                    Self::SiPrefix,
+                ] {
+                    let selected = self.label() == format.label();
+                    if ui.selectable_label(selected, format.label()).clicked() {
+                        *self = format;
+                    }
+                }
+            });
+        if let Self::Fixed(decimals) = self {
+            ui.add(
+                egui::DragValue::new(decimals)
+                    .range(0..=10)
+                    .suffix(" decimals"),
+            );
+        }
+    }
+}
+
+/// `value` scaled to an SI prefix, the same prefixes `FloatInput` accepts
+fn si_prefixed(value: f64) -> String {
+    if value == 0.0 || !value.is_finite() {
+        return format!("{}", value);
+    }
+    let exponent = ((value.abs().log10() / 3.0).floor() as i32).clamp(-3, 3);
+    let prefix = match exponent {
+        -3 => "n",
+        -2 => "µ",
+        -1 => "m",
+        0 => "",
+        1 => "k",
+        2 => "M",
+        _ => "G",
+    };
+    let scaled = format!("{:.3}", value / 10f64.powi(3 * exponent));
+    let scaled = scaled.trim_end_matches('0').trim_end_matches('.');
+    format!("{}{}", scaled, prefix)
+}
+
+/// tick at a fixed position, the label replaces the number if given
+pub struct Tick {
+    pub value: f64,
+    pub label: Option<String>,
+}
+
+/// ticks from a comma separated list like `0, 3.1416=π, 6.2832=2π`,
+/// entries that do not start with a number are skipped
+pub fn parse_ticks(spec: &str) -> Vec<Tick> {
+    spec.split(',')
+        .filter_map(|entry| {
+            let (value, label) = match entry.split_once('=') {
+                Some((value, label)) => (value, Some(label.trim().to_string())),
+                None => (entry, None),
+            };
+            Some(Tick {
+                value: value.trim().parse().ok()?,
+                label,
+            })
+        })
+        .collect()
+}
+
+/// label of the tick at `value`, if there is one
+pub fn tick_label(ticks: &[Tick], value: f64) -> Option<String> {
+    ticks
+        .iter()
+        // exported values pass through f32
+        .find(|tick| (tick.value - value).abs() <= 1e-6 * tick.value.abs().max(1.0))
+        .map(|tick| {
+            tick.label
+                .clone()
+                .unwrap_or_else(|| format!("{}", tick.value))
+        })
+}
+
+/// egui_plot only knows increasing axes, reversed axes are drawn by negating
+/// the coordinates of everything in the plot and the labels of the ticks,
+/// negating again maps back to data coordinates
+#[derive(Clone, Copy)]
+pub struct AxisFlip {
+    x: bool,
+    y: bool,
+}
+
+impl AxisFlip {
+    pub fn new(options: &PlotOptions) -> Self {
+        Self {
+            x: options.x_reversed,
+            y: options.y_reversed,
+        }
+    }
+    fn sign(reversed: bool) -> f64 {
+        if reversed {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+    pub fn point(&self, [x, y]: [f64; 2]) -> [f64; 2] {
+        [x * Self::sign(self.x), y * Self::sign(self.y)]
+    }
+    pub fn plot_point(&self, point: egui_plot::PlotPoint) -> egui_plot::PlotPoint {
+        let [x, y] = self.point([point.x, point.y]);
+        egui_plot::PlotPoint::new(x, y)
+    }
+    pub fn points(&self, points: Vec<[f64; 2]>) -> egui_plot::PlotPoints {
+        egui_plot::PlotPoints::new(points.into_iter().map(|p| self.point(p)).collect())
+    }
+    pub fn bounds(&self, bounds: egui_plot::PlotBounds) -> egui_plot::PlotBounds {
+        let (a, b) = (self.point(bounds.min()), self.point(bounds.max()));
+        egui_plot::PlotBounds::from_min_max(
+            [a[0].min(b[0]), a[1].min(b[1])],
+            [a[0].max(b[0]), a[1].max(b[1])],
+        )
+    }
+}
+
+/// replace the automatic grid of `plot` by the custom ticks of `options`,
+/// and the tick labels by its number formats
+fn with_custom_ticks<'a>(
+    mut plot: egui_plot::Plot<'a>,
+    options: &PlotOptions,
+    flip: AxisFlip,
+) -> egui_plot::Plot<'a> {
+    fn spacer(
+        ticks: &[Tick],
+        sign: f64,
+    ) -> impl Fn(egui_plot::GridInput) -> Vec<egui_plot::GridMark> {
+        let values: Vec<f64> = ticks.iter().map(|tick| tick.value * sign).collect();
+        move |input| {
+            // a large step size makes egui_plot draw every label
+            let step_size = (input.bounds.1 - input.bounds.0).abs();
+            values
+                .iter()
+                .map(|value| egui_plot::GridMark {
+                    value: *value,
+                    step_size,
+                })
+                .collect()
+        }
+    }
+    fn formatter(
+        ticks: Vec<Tick>,
+        format: TickFormat,
+        sign: f64,
+    ) -> impl Fn(egui_plot::GridMark, &std::ops::RangeInclusive<f64>) -> String {
+        move |mark, _| {
+            let value = mark.value * sign;
+            if !ticks.is_empty() {
+                return tick_label(&ticks, value).unwrap_or_default();
+            }
+            format.format(value).unwrap_or_else(|| {
+                // the default of egui_plot
+                let num_decimals = -mark.step_size.log10().round() as usize;
+                egui::emath::format_with_decimals_in_range(value, num_decimals..=num_decimals)
+            })
+        }
+    }
+    /// grid lines every `spacing` in data coordinates
+    fn spaced(
+        spacing: f64,
+        sign: f64,
+    ) -> impl Fn(egui_plot::GridInput) -> Vec<egui_plot::GridMark> {
+        move |input| {
+            let (a, b) = (input.bounds.0 * sign, input.bounds.1 * sign);
+            spaced_ticks(spacing, a.min(b), a.max(b))
+                .into_iter()
+                .map(|value| egui_plot::GridMark {
+                    value: value * sign,
+                    step_size: spacing,
+                })
+                .collect()
+        }
+    }
+    let grid = options.grid;
+    plot = plot.show_grid(grid.show);
+    let x_ticks = parse_ticks(&options.x_ticks);
+    let x_sign = AxisFlip::sign(flip.x);
+    if !x_ticks.is_empty() {
+        plot = plot.x_grid_spacer(spacer(&x_ticks, x_sign));
+    } else if grid.x_spacing > 0.0 {
+        plot = plot.x_grid_spacer(spaced(grid.x_spacing, x_sign));
+    }
+    if !x_ticks.is_empty() || options.x_format != TickFormat::Auto || flip.x {
+        plot = plot.x_axis_formatter(formatter(x_ticks, options.x_format, x_sign));
+    }
+    let y_ticks = parse_ticks(&options.y_ticks);
+    let y_sign = AxisFlip::sign(flip.y);
+    if !y_ticks.is_empty() {
+        plot = plot.y_grid_spacer(spacer(&y_ticks, y_sign));
+    } else if grid.y_spacing > 0.0 {
+        plot = plot.y_grid_spacer(spaced(grid.y_spacing, y_sign));
+    }
+    if !y_ticks.is_empty() || options.y_format != TickFormat::Auto || flip.y {
+        plot = plot.y_axis_formatter(formatter(y_ticks, options.y_format, y_sign));
+    }
+    if flip.x || flip.y {
+        // the coordinates shown next to the pointer
+        plot = plot.label_formatter(move |name, point| {
+            let point = flip.plot_point(*point);
+            let coordinates = format!("x = {:.3}\ny = {:.3}", point.x, point.y);
+            if name.is_empty() {
+                coordinates
+            } else {
+                format!("{}\n{}", name, coordinates)
+            }
+        });
+    }
+    plot
+}
+
+/// keys that, held together with the primary mouse button, start a drag
+/// manipulation of the active files
+#[derive(Serialize, Deserialize, Clone)]
+pub struct KeyBindings {
+    pub scale_y: egui::Key,
+    pub offset_y: egui::Key,
+    pub offset_x: egui::Key,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self {
+            scale_y: egui::Key::F,
+            offset_y: egui::Key::D,
+            offset_x: egui::Key::G,
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        egui::Grid::new("key_bindings")
+            .num_columns(2)
+            .show(ui, |ui| {
+                for (label, key) in [
+                    ("Scale y", &mut self.scale_y),
+                    ("Offset y", &mut self.offset_y),
+                    ("Offset x", &mut self.offset_x),
+                ] {
+                    ui.label(label);
+                    egui::ComboBox::from_id_source(label)
+                        .selected_text(key.name())
+                        .show_ui(ui, |ui| {
+                            for candidate in egui::Key::ALL {
+                                ui.selectable_value(key, *candidate, candidate.name());
+                            }
+                        });
+                    ui.end_row();
+                }
+            });
+        if self.scale_y == self.offset_y
+            || self.scale_y == self.offset_x
+            || self.offset_y == self.offset_x
+        {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                "Several manipulations share a key.",
+            );
+        }
+        if ui.button("Reset Key Bindings").clicked() {
+            *self = KeyBindings::default();
+        }
+    }
+}
+
+/// offsets added to the manipulated files per press of Alt + arrow key, in
+/// data units independent of the view
+#[derive(Serialize, Deserialize, Clone, Copy)]
+pub struct NudgeStep {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Default for NudgeStep {
+    fn default() -> Self {
+        Self { x: 0.1, y: 0.1 }
+    }
+}
+
+impl NudgeStep {
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.label("Alt + arrow keys shift the active files by");
+        egui::Grid::new("nudge_step").num_columns(2).show(ui, |ui| {
+            ui.label("x");
+            ui.add(egui::DragValue::new(&mut self.x).speed(0.01));
+            ui.end_row();
+            ui.label("y");
+            ui.add(egui::DragValue::new(&mut self.y).speed(0.01));
+            ui.end_row();
+        });
+    }
+}
+
+/// separator between x and y when copying the values at the cursor
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CopySeparator {
+    /// pastes into separate spreadsheet cells
+    #[default]
+    Tab,
+    Comma,
+}
+
+impl CopySeparator {
+    pub const ALL: [CopySeparator; 2] = [Self::Tab, Self::Comma];
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Tab => "Tab",
+            Self::Comma => "Comma",
+        }
+    }
+    fn separator(&self) -> &'static str {
+        match self {
+            Self::Tab => "\t",
+            Self::Comma => ",",
+        }
+    }
+}
+
 impl PlotDimensions {
     pub fn xspan(&self) -> f32 {
         (self.x1 - self.x0).abs()
@@ -20,124 +673,1095 @@ impl PlotDimensions {
     }
 }
 
+/// plotted files whose colors are hard to tell apart, recomputed only when
+/// the plotted files or their colors change
+#[derive(Default)]
+pub struct ColorCheck {
+    plotted: Vec<(usize, Color32)>,
+    similar: Vec<(usize, usize)>,
+}
+
 impl App {
-    pub fn plot_panel_ui(&mut self, ctx: &egui::Context) {
-        egui::panel::CentralPanel::default().show(ctx, |ui| {
-            // read input events
-            let (d_down, f_down, g_down, mouse_delta) = ctx.input(|i| {
-                // set acceleration if mouse is pressed
-                if i.pointer.primary_pressed() {
-                    self.acceleration = Some(1.0)
-                };
-                // increase acceleration by x % per frame if mouse button is down
-                if i.pointer.primary_down() {
-                    self.acceleration = self.acceleration.map(|acc| acc * 1.03);
-                }
-                (
-                    i.key_down(egui::Key::D) && i.pointer.primary_down(), // pan y
-                    i.key_down(egui::Key::F) && i.pointer.primary_down(), // scale y
-                    i.key_down(egui::Key::G) && i.pointer.primary_down(), // pan x
-                    i.pointer.delta(),
-                )
-            });
-            // scale active plots along y
-            if !d_down && f_down && mouse_delta.y != 0.0 {
-                for file_entry in self.folders.iter_mut().flat_map(|folder| &mut folder.files) {
-                    if !file_entry.is_active() {
-                        continue;
-                    }
-                    if let Some(scale) = file_entry.scale.parse() {
-                        let acceleration = self.acceleration.unwrap_or(1.0) as f32;
-                        let scale = scale as f32;
-                        // we just modify the string ... hacky
-                        file_entry.scale.input = format!(
-                            "{}",
-                            scale - mouse_delta.y.signum() * scale * 0.01 * acceleration
-                        );
-                    }
+    fn update_color_check(&mut self) {
+        // only colors that end up in the same plot need to be distinguishable
+        let tab = self.plot_tabs.active().id;
+        let plotted: Vec<(usize, Color32)> = self
+            .folders
+            .iter()
+            .flat_map(|folder| &folder.files)
+            .filter(|file_entry| {
+                file_entry.is_drawn_in(tab) && file_entry.color != Color32::TRANSPARENT
+            })
+            .map(|file_entry| (file_entry.id, file_entry.color))
+            .collect();
+        if plotted == self.color_check.plotted {
+            return;
+        }
+        let mut similar = vec![];
+        for (i, (id_a, color_a)) in plotted.iter().enumerate() {
+            for (id_b, color_b) in &plotted[i + 1..] {
+                if color_distance(*color_a, *color_b) < SIMILAR_COLOR_DISTANCE {
+                    similar.push((*id_a, *id_b));
                 }
             }
-            // offset active plots along y
-            if d_down && !f_down && mouse_delta.y != 0.0 {
-                for file_entry in self.folders.iter_mut().flat_map(|folder| &mut folder.files) {
-                    if file_entry.is_active() {
-                        continue;
-                    }
-                    if let Some(offset) = file_entry.offset.parse() {
-                        let acceleration = self.acceleration.unwrap_or(1.0) as f32;
-                        let offset = offset as f32;
-                        let span = self.plot_dims.yspan();
-                        // we just modify the string ... hacky
-                        file_entry.offset.input = format!(
-                            "{}",
-                            offset - mouse_delta.y.signum() * span * 0.001 * acceleration
-                        );
-                    }
+        }
+        self.color_check = ColorCheck { plotted, similar };
+    }
+
+    /// names of the files the drag manipulations apply to
+    fn active_files_ui(&self, ui: &mut egui::Ui) {
+        let tab = self.plot_tabs.active().id;
+        let active: Vec<&FileEntry> = self
+            .folders
+            .iter()
+            .flat_map(|folder| &folder.files)
+            .filter(|file_entry| file_entry.is_active() && file_entry.tab == Some(tab))
+            .collect();
+        if active.is_empty() {
+            return;
+        }
+        ui.horizontal(|ui| {
+            ui.label("Active:");
+            for file_entry in active {
+                ui.label(
+                    egui::RichText::new(file_entry.display_name())
+                        .strong()
+                        .color(file_entry.color),
+                );
+            }
+            ui.weak("(Tab / Shift+Tab to cycle)");
+        });
+    }
+
+    fn similar_colors_ui(&mut self, ui: &mut egui::Ui) {
+        if self.color_check.similar.is_empty() {
+            return;
+        }
+        let filename = |id: usize| {
+            self.folders
+                .iter()
+                .flat_map(|folder| &folder.files)
+                .find(|file_entry| file_entry.id == id)
+                .map(|file_entry| file_entry.display_name())
+                .unwrap_or_default()
+        };
+        let pairs: Vec<String> = self
+            .color_check
+            .similar
+            .iter()
+            .map(|(a, b)| format!("{} / {}", filename(*a), filename(*b)))
+            .collect();
+        let mut recolor = false;
+        ui.horizontal(|ui| {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("Similar colors: {}", pairs.join(", ")),
+            );
+            recolor = ui.small_button("Recolor").clicked();
+        });
+        if recolor {
+            // reassign the second file of each pair from the next palette slot
+            let ids: Vec<usize> = self.color_check.similar.iter().map(|(_, b)| *b).collect();
+            for file_entry in self.folders.iter_mut().flat_map(|folder| &mut folder.files) {
+                if ids.contains(&file_entry.id) {
+                    file_entry.color = next_auto_color(ui.ctx(), &self.color_cycle);
                 }
             }
-            // offset active plots along x
-            if g_down && mouse_delta.x != 0.0 {
-                for file_entry in self.folders.iter_mut().flat_map(|folder| &mut folder.files) {
-                    if file_entry.is_active() {
-                        continue;
-                    }
-                    if let Some(xoffset) = file_entry.xoffset.parse() {
-                        let acceleration = self.acceleration.unwrap_or(1.0) as f32;
-                        let xoffset = xoffset as f32;
-                        let span = self.plot_dims.xspan();
-                        // we just modify the string ... hacky
-                        file_entry.xoffset.input = format!(
-                            "{}",
-                            xoffset + mouse_delta.x.signum() * span * 0.001 * acceleration
+        }
+    }
+
+    pub fn plot_options_ui(&mut self, ui: &mut egui::Ui) {
+        let options = &mut self.plot_options;
+        let lab = ui.label("Title");
+        ui.text_edit_singleline(&mut options.title)
+            .labelled_by(lab.id);
+        ui.horizontal(|ui| {
+            ui.label("Font");
+            egui::ComboBox::from_id_source("title_font_family")
+                .selected_text(&options.title_font_family)
+                .show_ui(ui, |ui| {
+                    for family in ["sans-serif", "serif", "monospace"] {
+                        ui.selectable_value(
+                            &mut options.title_font_family,
+                            family.to_string(),
+                            family,
                         );
                     }
+                });
+            ui.add(
+                egui::DragValue::new(&mut options.title_font_size)
+                    .range(4.0..=100.0)
+                    .suffix(" px"),
+            );
+        });
+        ui.separator();
+        ui.label("Custom ticks, e.g. `0, 3.1416=π, 6.2832=2π`")
+            .on_hover_text("Leave empty for automatic ticks.");
+        egui::Grid::new("custom_ticks")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("x");
+                ui.text_edit_singleline(&mut options.x_ticks);
+                ui.end_row();
+                ui.label("y");
+                ui.text_edit_singleline(&mut options.y_ticks);
+                ui.end_row();
+            });
+        ui.label("Number format of the tick labels");
+        egui::Grid::new("tick_formats")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("x");
+                ui.horizontal(|ui| options.x_format.ui(ui, "x_format"));
+                ui.end_row();
+                ui.label("y");
+                ui.horizontal(|ui| options.y_format.ui(ui, "y_format"));
+                ui.end_row();
+            });
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("Reverse");
+            ui.checkbox(&mut options.x_reversed, "x-axis");
+            ui.checkbox(&mut options.y_reversed, "y-axis");
+        });
+        ui.checkbox(&mut options.show_minimap, "Show minimap")
+            .on_hover_text("Overview of all plotted data, drag the view rectangle to pan");
+        ui.checkbox(&mut options.link_cursor, "Link x-cursor")
+            .on_hover_text("Mark the x-position of the pointer in all panels sharing the x-axis");
+        ui.checkbox(&mut options.show_point_index, "Show point index on hover")
+            .on_hover_text(
+                "Index of the closest point of the hovered line,\n\
+                 and its file row if the file remembers rows",
+            );
+        ui.separator();
+        options.grid.ui(ui);
+    }
+
+    /// tab ids of the files, newly plotted files join the active tab
+    fn assign_plot_tabs(&mut self) {
+        let active = self.plot_tabs.active().id;
+        for file_entry in self.folders.iter_mut().flat_map(|folder| &mut folder.files) {
+            if !file_entry.is_plotted() {
+                file_entry.tab = None;
+            } else if !file_entry
+                .tab
+                .is_some_and(|tab| self.plot_tabs.contains(tab))
+            {
+                file_entry.tab = Some(active);
+            }
+        }
+    }
+
+    /// average the files drawn in the active tab into a new curve
+    fn average_plotted(&mut self, ctx: &egui::Context) {
+        let tab = self.plot_tabs.active().id;
+        let series: Vec<Vec<[f64; 2]>> = self
+            .folders
+            .iter()
+            .flat_map(|folder| &folder.files)
+            .filter(|file_entry| file_entry.is_drawn_in(tab))
+            .map(|file_entry| file_entry.transformed_data())
+            .collect();
+        let num_series = series.len();
+        let Some(points) = average(&series, self.average_range) else {
+            self.errors.push(
+                "WARNING: averaging needs at least two plotted files with overlapping x-ranges."
+                    .to_string(),
+            );
+            return;
+        };
+        self.averages.push(AverageCurve {
+            name: format!("Average of {} files", num_series),
+            tab,
+            color: next_auto_color(ctx, &self.color_cycle),
+            points,
+            show_band: true,
+        });
+    }
+
+    pub fn averages_ui(&mut self, ui: &mut egui::Ui) {
+        egui::ComboBox::from_id_source("average_range")
+            .selected_text(self.average_range.label())
+            .show_ui(ui, |ui| {
+                for range in AverageRange::ALL {
+                    ui.selectable_value(&mut self.average_range, range, range.label());
+                }
+            });
+        if ui
+            .button("Average Plotted")
+            .on_hover_text("Add the mean curve of the files in the active plot tab")
+            .clicked()
+        {
+            self.average_plotted(ui.ctx());
+        }
+        let mut removed = None;
+        for (idx, curve) in self.averages.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.small_button("x").clicked() {
+                    removed = Some(idx);
                 }
+                egui::color_picker::color_edit_button_srgba(
+                    ui,
+                    &mut curve.color,
+                    egui::color_picker::Alpha::Opaque,
+                );
+                ui.text_edit_singleline(&mut curve.name);
+                ui.checkbox(&mut curve.show_band, "± std");
+            });
+        }
+        if let Some(idx) = removed {
+            self.averages.remove(idx);
+        }
+    }
+
+    pub fn plot_settings_clipboard_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            if ui
+                .button("Copy Plot Settings")
+                .on_hover_text("Title, ticks, bounds, export style and colors")
+                .clicked()
+            {
+                self.copied_plot_settings = Some(PlotSettings {
+                    options: self.plot_options.clone(),
+                    bounds: self.plot_tabs.active().dims,
+                    export_style: self.export_style.clone(),
+                    color_cycle: self.color_cycle.clone(),
+                });
             }
-            egui_plot::Plot::new(1)
-                .min_size(egui::Vec2 { x: 640.0, y: 480.0 })
-                .allow_drag(!(f_down || d_down || g_down))
-                .show(ui, |plot_ui| {
-                    // update plot dimensions in App state
-                    let [x0, y0] = plot_ui.plot_bounds().min();
-                    let [x1, y1] = plot_ui.plot_bounds().max();
-                    self.plot_dims.x0 = x0 as f32;
-                    self.plot_dims.x1 = x1 as f32;
-                    self.plot_dims.y0 = y0 as f32;
-                    self.plot_dims.y1 = y1 as f32;
-                    for file_entry in self.folders.iter_mut().flat_map(|folder| &mut folder.files) {
-                        if !file_entry.is_plotted() {
-                            continue;
-                        }
-                        if file_entry.color == Color32::TRANSPARENT {
-                            {
-                                // if no color was assigned to file yet, generate
-                                // it from the running color index
-                                let color_idx = ctx.data_mut(|map| {
-                                    let idx =
-                                        map.get_temp_mut_or_insert_with(Id::new("color_idx"), || 0);
-                                    *idx += 1;
-                                    *idx
-                                });
-                                file_entry.color = auto_color(color_idx);
+            let paste = ui
+                .add_enabled(
+                    self.copied_plot_settings.is_some(),
+                    egui::Button::new("Paste Plot Settings"),
+                )
+                .clicked();
+            if let (true, Some(settings)) = (paste, &self.copied_plot_settings) {
+                let bounds = settings.bounds;
+                self.plot_options = settings.options.clone();
+                self.export_style = settings.export_style.clone();
+                self.color_cycle = settings.color_cycle.clone();
+                self.pending_bounds = Some(egui_plot::PlotBounds::from_min_max(
+                    [bounds.x0 as f64, bounds.y0 as f64],
+                    [bounds.x1 as f64, bounds.y1 as f64],
+                ));
+            }
+        });
+    }
+
+    /// pan and zoom the active tab so that all of its data is visible
+    pub fn fit_to_window(&mut self) {
+        let tab = self.plot_tabs.active().id;
+        let Some(([x0, x1], [y0, y1])) = data_extent(&self.folders, tab) else {
+            self.errors
+                .push("WARNING: no plotted data to fit the view to.".to_string());
+            return;
+        };
+        let (xmargin, ymargin) = (view_margin(x0, x1), view_margin(y0, y1));
+        self.pending_bounds = Some(egui_plot::PlotBounds::from_min_max(
+            [x0 - xmargin, y0 - ymargin],
+            [x1 + xmargin, y1 + ymargin],
+        ));
+    }
+
+    pub fn annotations_ui(&mut self, ui: &mut egui::Ui) {
+        let tab = self.plot_tabs.active().id;
+        ui.horizontal(|ui| {
+            if ui
+                .button("Add")
+                .on_hover_text("Add a label in the center of the plot")
+                .clicked()
+            {
+                let dims = &self.plot_tabs.active().dims;
+                self.annotations.push(TextAnnotation {
+                    x: ((dims.x0 + dims.x1) / 2.0) as f64,
+                    y: ((dims.y0 + dims.y1) / 2.0) as f64,
+                    text: "label".to_string(),
+                    color: ui.visuals().text_color(),
+                    font_size: 14.0,
+                    tab,
+                });
+            }
+            ui.toggle_value(&mut self.placing_annotation, "Place by Clicking")
+                .on_hover_text("The next click into the plot adds a label there");
+        });
+        let mut removed = None;
+        for (idx, annotation) in self
+            .annotations
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, annotation)| annotation.tab == tab)
+        {
+            ui.horizontal(|ui| {
+                if ui.small_button("x").clicked() {
+                    removed = Some(idx);
+                }
+                egui::color_picker::color_edit_button_srgba(
+                    ui,
+                    &mut annotation.color,
+                    egui::color_picker::Alpha::Opaque,
+                );
+                ui.text_edit_singleline(&mut annotation.text);
+                ui.add(
+                    egui::DragValue::new(&mut annotation.x)
+                        .speed(0.1)
+                        .prefix("x "),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut annotation.y)
+                        .speed(0.1)
+                        .prefix("y "),
+                );
+                ui.add(
+                    egui::DragValue::new(&mut annotation.font_size)
+                        .range(4.0..=100.0)
+                        .suffix(" px"),
+                );
+            });
+        }
+        if let Some(idx) = removed {
+            self.annotations.remove(idx);
+        }
+    }
+
+    /// shift the manipulated files of the active tab by `nudge_step` with
+    /// Alt + arrow keys
+    pub fn nudge_offsets(&mut self, ctx: &egui::Context) {
+        use egui::{Key, Modifiers};
+        if ctx.wants_keyboard_input() {
+            return;
+        }
+        let (dx, dy) = ctx.input_mut(|i| {
+            let mut count = |key| i.count_and_consume_key(Modifiers::ALT, key) as f64;
+            (
+                count(Key::ArrowRight) - count(Key::ArrowLeft),
+                count(Key::ArrowUp) - count(Key::ArrowDown),
+            )
+        });
+        if dx == 0.0 && dy == 0.0 {
+            return;
+        }
+        let flip = AxisFlip::new(&self.plot_options);
+        // the keys move the curves on screen, which is mirrored on reversed axes
+        let dx = dx * self.nudge_step.x * AxisFlip::sign(flip.x);
+        let dy = dy * self.nudge_step.y * AxisFlip::sign(flip.y);
+        for file_entry in self.manipulated_files_mut() {
+            if let (Some(xoffset), true) = (file_entry.xoffset.parse(), dx != 0.0) {
+                file_entry.xoffset.input = format!("{}", xoffset + dx);
+            }
+            if let (Some(offset), true) = (file_entry.offset.parse(), dy != 0.0) {
+                // relative offsets are converted, so that the step stays absolute
+                let dy = match file_entry.offset_mode {
+                    OffsetMode::Absolute => dy,
+                    OffsetMode::FractionOfRange => match file_entry.stats() {
+                        Some(stats) if stats.ymax > stats.ymin => dy / (stats.ymax - stats.ymin),
+                        _ => continue,
+                    },
+                };
+                file_entry.offset.input = format!("{}", offset + dy);
+            }
+        }
+    }
+
+    /// the files of the active tab that are moved by drags and nudges
+    fn manipulated_files_mut(&mut self) -> impl Iterator<Item = &mut FileEntry> {
+        let targets = drag_targets(
+            self.folders.iter().flat_map(|folder| &folder.files),
+            self.plot_tabs.active().id,
+            self.manipulate_primary_only,
+            self.primary_file,
+        );
+        self.folders
+            .iter_mut()
+            .flat_map(|folder| &mut folder.files)
+            .filter(move |file_entry| targets.contains(&file_entry.id))
+    }
+
+    /// scale or offset the manipulated files by a drag of the pointer by
+    /// `mouse_delta` with the keys of the gestures held down
+    fn apply_drag(
+        &mut self,
+        offset_y: bool,
+        scale_y: bool,
+        offset_x: bool,
+        mouse_delta: egui::Vec2,
+        step_factor: f32,
+    ) {
+        let flip = AxisFlip::new(&self.plot_options);
+        let acceleration = self.acceleration.unwrap_or(1.0) as f32 * step_factor;
+        let (xspan, yspan) = {
+            let dims = &self.plot_tabs.active().dims;
+            (dims.xspan(), dims.yspan())
+        };
+        // scale active plots along y
+        if !offset_y && scale_y && mouse_delta.y != 0.0 {
+            for file_entry in self.manipulated_files_mut() {
+                if let Some(scale) = file_entry.scale.parse() {
+                    let scale = scale as f32;
+                    // we just modify the string ... hacky
+                    file_entry.scale.input = format!(
+                        "{}",
+                        scale - mouse_delta.y.signum() * scale * 0.01 * acceleration
+                    );
+                }
+            }
+        }
+        // offset active plots along y
+        if offset_y && !scale_y && mouse_delta.y != 0.0 {
+            for file_entry in self.manipulated_files_mut() {
+                if let Some(offset) = file_entry.offset.parse() {
+                    let offset = offset as f32;
+                    let mut span = yspan;
+                    // relative offsets move by the same distance on screen
+                    if file_entry.offset_mode == OffsetMode::FractionOfRange {
+                        match file_entry.stats() {
+                            Some(stats) if stats.ymax > stats.ymin => {
+                                span /= (stats.ymax - stats.ymin) as f32
                             }
+                            _ => continue,
                         }
-                        let scale = file_entry.scale.parse().unwrap_or(1.0);
-                        let offset = file_entry.offset.parse().unwrap_or(0.0);
-                        let xoffset = file_entry.xoffset.parse().unwrap_or(0.0);
-                        let input_data = file_entry
-                            .data_file
-                            .data
-                            .iter()
-                            .map(|[x, y]| [*x + xoffset, *y * scale + offset])
-                            .collect();
-                        let line = egui_plot::Line::new(egui_plot::PlotPoints::new(input_data))
+                    }
+                    // we just modify the string ... hacky
+                    file_entry.offset.input = format!(
+                        "{}",
+                        offset
+                            - mouse_delta.y.signum()
+                                * AxisFlip::sign(flip.y) as f32
+                                * span
+                                * 0.001
+                                * acceleration
+                    );
+                }
+            }
+        }
+        // offset active plots along x
+        if offset_x && mouse_delta.x != 0.0 {
+            for file_entry in self.manipulated_files_mut() {
+                if let Some(xoffset) = file_entry.xoffset.parse() {
+                    let xoffset = xoffset as f32;
+                    // we just modify the string ... hacky
+                    file_entry.xoffset.input = format!(
+                        "{}",
+                        xoffset
+                            + mouse_delta.x.signum()
+                                * AxisFlip::sign(flip.x) as f32
+                                * xspan
+                                * 0.001
+                                * acceleration
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn plot_panel_ui(&mut self, ctx: &egui::Context) {
+        egui::panel::CentralPanel::default().show(ctx, |ui| {
+            self.plot_tabs.ui(ui);
+            self.assign_plot_tabs();
+            self.update_color_check();
+            self.similar_colors_ui(ui);
+            self.active_files_ui(ui);
+            if self.plot_options.show_minimap {
+                self.minimap_ui(ui);
+            }
+            self.draw_plot(ctx, ui);
+        });
+    }
+
+    /// the plot of the active tab, with the drag gestures and hover
+    /// information of its files
+    pub fn draw_plot(&mut self, ctx: &egui::Context, ui: &mut egui::Ui) {
+        let tab = self.plot_tabs.active().id;
+        let flip = AxisFlip::new(&self.plot_options);
+        // read input events
+        let typing = ctx.wants_keyboard_input();
+        let keys = &self.key_bindings;
+        let mut copy_values = false;
+        let mut fit_view = false;
+        let mut drag_mode = None;
+        let mut point_info = None;
+        let (d_down, f_down, g_down, mouse_delta, autoscale_y, step_factor) = ctx.input(|i| {
+            copy_values = !typing && i.key_pressed(egui::Key::C);
+            fit_view = !typing && i.key_pressed(egui::Key::Home);
+            if !typing {
+                drag_mode = DragMode::from_keys(
+                    i.key_down(keys.offset_y),
+                    i.key_down(keys.scale_y),
+                    i.key_down(keys.offset_x),
+                );
+            }
+            // set acceleration if mouse is pressed
+            if i.pointer.primary_pressed() {
+                self.acceleration = Some(1.0)
+            };
+            // increase acceleration by x % per frame if mouse button is down
+            if i.pointer.primary_down() {
+                self.acceleration = self.acceleration.map(|acc| acc * 1.03);
+            }
+            (
+                i.key_down(keys.offset_y) && i.pointer.primary_down(), // pan y
+                i.key_down(keys.scale_y) && i.pointer.primary_down(),  // scale y
+                i.key_down(keys.offset_x) && i.pointer.primary_down(), // pan x
+                i.pointer.delta(),
+                !typing && i.key_pressed(egui::Key::Y), // fit y to visible x-range
+                drag_step_factor(i.modifiers),
+            )
+        });
+        if fit_view {
+            self.fit_to_window();
+        }
+        self.apply_drag(d_down, f_down, g_down, mouse_delta, step_factor);
+        // every tab keeps its own view in the egui memory
+        let plot_response = with_custom_ticks(
+            egui_plot::Plot::new(("plot", tab)),
+            &self.plot_options,
+            flip,
+        )
+        .min_size(egui::Vec2 { x: 640.0, y: 480.0 })
+        .allow_drag(!(f_down || d_down || g_down))
+        .show(ui, |plot_ui| {
+            if autoscale_y {
+                let bounds = flip.bounds(plot_ui.plot_bounds());
+                let [x0, _] = bounds.min();
+                let [x1, _] = bounds.max();
+                // leave bounds unchanged if there is no data in the x-window
+                if let Some((y0, y1)) = visible_y_range(&self.folders, tab, x0, x1) {
+                    let margin = view_margin(y0, y1);
+                    plot_ui.set_plot_bounds(flip.bounds(egui_plot::PlotBounds::from_min_max(
+                        [x0, y0 - margin],
+                        [x1, y1 + margin],
+                    )));
+                }
+            }
+            if let Some(bounds) = self.pending_bounds.take() {
+                plot_ui.set_plot_bounds(flip.bounds(bounds));
+            }
+            // update plot dimensions in App state
+            let bounds = flip.bounds(plot_ui.plot_bounds());
+            let [x0, y0] = bounds.min();
+            let [x1, y1] = bounds.max();
+            let plot_dims = &mut self.plot_tabs.active_mut().dims;
+            plot_dims.x0 = x0 as f32;
+            plot_dims.x1 = x1 as f32;
+            plot_dims.y0 = y0 as f32;
+            plot_dims.y1 = y1 as f32;
+            let hovered = nearest_line(plot_ui, &self.folders, tab, flip);
+            if self.plot_options.show_point_index {
+                point_info = point_at_cursor(plot_ui, &mut self.folders, hovered, flip);
+            }
+            if copy_values && plot_ui.response().hovered() {
+                if let Some([x, y]) = values_at_cursor(plot_ui, &self.folders, hovered, flip) {
+                    let text = format!("{}{}{}", x, self.copy_separator.separator(), y);
+                    self.errors
+                        .push(format!("INFO: copied {} to the clipboard.", text));
+                    ctx.output_mut(|o| o.copied_text = text);
+                }
+            }
+            for file_entry in self.folders.iter_mut().flat_map(|folder| &mut folder.files) {
+                file_entry.hovered_in_plot = hovered == Some(file_entry.id);
+                if !file_entry.is_plotted() || file_entry.tab != Some(tab) {
+                    continue;
+                }
+                if file_entry.color == Color32::TRANSPARENT {
+                    // if no color was assigned to file yet, generate
+                    // it from the running color index
+                    file_entry.color = next_auto_color(ctx, &self.color_cycle);
+                }
+                if !file_entry.visible {
+                    continue;
+                }
+                if file_entry.show_original {
+                    let line = egui_plot::Line::new(flip.points(file_entry.data_file.data.clone()))
+                        .color(file_entry.color.gamma_multiply(0.3))
+                        .width(1.0);
+                    plot_ui.line(line);
+                }
+                if file_entry.fill {
+                    // egui only fills convex polygons
+                    let fill_color = file_entry.color.gamma_multiply(file_entry.fill_alpha);
+                    for polygon in file_entry.fill_polygons() {
+                        let polygon = egui_plot::Polygon::new(flip.points(polygon.clone()))
+                            .fill_color(fill_color)
+                            .stroke(egui::Stroke::NONE);
+                        plot_ui.polygon(polygon);
+                    }
+                }
+                for segment in file_entry.line_segments() {
+                    let mut line = egui_plot::Line::new(flip.points(segment))
+                        .color(file_entry.color)
+                        .highlight(file_entry.is_active());
+                    if file_entry.hovered_in_plot {
+                        line = line.width(3.0);
+                    }
+                    plot_ui.line(line);
+                }
+                if file_entry.render_mode == RenderMode::LinePoints {
+                    let points = egui_plot::Points::new(flip.points(file_entry.transformed_data()))
+                        .color(file_entry.color)
+                        .radius(file_entry.marker_size);
+                    plot_ui.points(points);
+                }
+                if file_entry.fit_linear {
+                    if let Some(fit_line) = file_entry.linear_fit_line() {
+                        let line = egui_plot::Line::new(flip.points(fit_line.to_vec()))
                             .color(file_entry.color)
-                            .highlight(file_entry.is_active());
+                            .style(egui_plot::LineStyle::dashed_loose());
                         plot_ui.line(line);
                     }
-                });
+                }
+            }
+            for curve in self.averages.iter().filter(|curve| curve.tab == tab) {
+                if curve.show_band {
+                    // egui only fills convex polygons
+                    for quad in curve.band_quads() {
+                        let polygon = egui_plot::Polygon::new(flip.points(quad.to_vec()))
+                            .fill_color(curve.color.gamma_multiply(0.2))
+                            .stroke(egui::Stroke::NONE);
+                        plot_ui.polygon(polygon);
+                    }
+                }
+                let line = egui_plot::Line::new(flip.points(curve.mean()))
+                    .color(curve.color)
+                    .name(&curve.name);
+                plot_ui.line(line);
+            }
+            for annotation in self.annotations.iter().filter(|a| a.tab == tab) {
+                let text = egui::RichText::new(&annotation.text)
+                    .size(annotation.font_size)
+                    .color(annotation.color);
+                plot_ui.text(egui_plot::Text::new(
+                    flip.plot_point(egui_plot::PlotPoint::new(annotation.x, annotation.y)),
+                    text,
+                ));
+            }
+            if self.plot_options.link_cursor {
+                // the plot tab is the only panel so far
+                match plot_ui.pointer_coordinate() {
+                    Some(pos) if plot_ui.response().hovered() => {
+                        self.shared_cursor.hover(tab, flip.plot_point(pos).x)
+                    }
+                    _ => self.shared_cursor.leave(tab),
+                }
+                if let Some(x) = self.shared_cursor.x() {
+                    let vline = egui_plot::VLine::new(flip.point([x, 0.0])[0])
+                        .color(ctx.style().visuals.weak_text_color())
+                        .width(1.0);
+                    plot_ui.vline(vline);
+                }
+            }
+            if self.placing_annotation && plot_ui.response().clicked() {
+                if let Some(pos) = plot_ui.pointer_coordinate() {
+                    let pos = flip.plot_point(pos);
+                    self.annotations.push(TextAnnotation {
+                        x: pos.x,
+                        y: pos.y,
+                        text: "label".to_string(),
+                        color: ctx.style().visuals.text_color(),
+                        font_size: 14.0,
+                        tab,
+                    });
+                    self.placing_annotation = false;
+                }
+            }
         });
+        if let Some(info) = point_info {
+            let response = &plot_response.response;
+            egui::show_tooltip_at_pointer(ctx, response.layer_id, response.id, |ui| ui.label(info));
+        }
+        // make the mode of the held manipulation key visible
+        if let Some(mode) = drag_mode {
+            let plot_rect = plot_response.response.rect;
+            if plot_response.response.hovered() {
+                ctx.set_cursor_icon(mode.cursor_icon());
+            }
+            let painter = ui.painter_at(plot_rect);
+            let galley = painter.layout_no_wrap(
+                mode.label().to_string(),
+                egui::FontId::proportional(14.0),
+                ui.visuals().strong_text_color(),
+            );
+            let badge = egui::Rect::from_min_size(
+                plot_rect.left_top() + egui::vec2(8.0, 8.0),
+                galley.size() + egui::vec2(12.0, 6.0),
+            );
+            painter.rect_filled(badge, 4.0, ui.visuals().extreme_bg_color);
+            painter.galley(
+                badge.min + egui::vec2(6.0, 3.0),
+                galley,
+                Color32::PLACEHOLDER,
+            );
+        }
+    }
+}
+
+/// drag manipulation selected by the held key, see `KeyBindings`
+#[derive(Clone, Copy)]
+enum DragMode {
+    ScaleY,
+    OffsetY,
+    OffsetX,
+}
+
+impl DragMode {
+    /// the mode shown while the keys are held, see `apply_drag`
+    fn from_keys(offset_y: bool, scale_y: bool, offset_x: bool) -> Option<Self> {
+        match (offset_y, scale_y, offset_x) {
+            (_, _, true) => Some(Self::OffsetX),
+            (false, true, _) => Some(Self::ScaleY),
+            (true, false, _) => Some(Self::OffsetY),
+            _ => None,
+        }
+    }
+    fn label(&self) -> &'static str {
+        match self {
+            Self::ScaleY => "Scale Y",
+            Self::OffsetY => "Offset Y",
+            Self::OffsetX => "Offset X",
+        }
     }
+    fn cursor_icon(&self) -> egui::CursorIcon {
+        match self {
+            Self::ScaleY => egui::CursorIcon::ResizeVertical,
+            Self::OffsetY => egui::CursorIcon::ResizeRow,
+            Self::OffsetX => egui::CursorIcon::ResizeColumn,
+        }
+    }
+}
+
+/// Shift dampens drag manipulations for fine adjustments, Ctrl (Cmd on
+/// macOS) boosts them
+fn drag_step_factor(modifiers: egui::Modifiers) -> f32 {
+    match (modifiers.shift, modifiers.command) {
+        (true, false) => 0.1,
+        (false, true) => 5.0,
+        _ => 1.0,
+    }
+}
+
+/// ids of the files drawn in `tab` that drags and nudges move
+fn drag_targets<'a>(
+    file_entries: impl IntoIterator<Item = &'a FileEntry>,
+    tab: usize,
+    primary_only: bool,
+    primary: Option<usize>,
+) -> Vec<usize> {
+    file_entries
+        .into_iter()
+        .filter(|file_entry| {
+            file_entry.tab == Some(tab) && is_manipulated(file_entry, primary_only, primary)
+        })
+        .map(|file_entry| file_entry.id)
+        .collect()
+}
+
+/// whether drag gestures apply to `file_entry`, `primary_only` restricts
+/// them to the `primary` file
+fn is_manipulated(file_entry: &FileEntry, primary_only: bool, primary: Option<usize>) -> bool {
+    file_entry.is_active() && (!primary_only || primary == Some(file_entry.id))
+}
+
+// lines further away from the pointer than this (in points) are not hovered
+const HOVER_DISTANCE: f32 = 8.0;
+
+/// id of the file drawn in `tab` whose line is closest to the pointer, the
+/// distance is measured on screen
+fn nearest_line(
+    plot_ui: &egui_plot::PlotUi,
+    folders: &[Folder],
+    tab: usize,
+    flip: AxisFlip,
+) -> Option<usize> {
+    if !plot_ui.response().hovered() {
+        return None;
+    }
+    let pointer = plot_ui.screen_from_plot(plot_ui.pointer_coordinate()?);
+    let mut nearest = None;
+    let mut nearest_distance = HOVER_DISTANCE;
+    for file_entry in folders
+        .iter()
+        .flat_map(|folder| &folder.files)
+        .filter(|file_entry| file_entry.is_drawn_in(tab))
+    {
+        for segment in file_entry.line_segments() {
+            let screen: Vec<egui::Pos2> = segment
+                .iter()
+                .filter(|[x, y]| x.is_finite() && y.is_finite())
+                .map(|[x, y]| {
+                    plot_ui.screen_from_plot(flip.plot_point(egui_plot::PlotPoint::new(*x, *y)))
+                })
+                .collect();
+            let distances = screen
+                .windows(2)
+                .map(|pair| point_segment_distance(pointer, pair[0], pair[1]))
+                // a single point has no line segment
+                .chain(
+                    screen
+                        .first()
+                        .filter(|_| screen.len() == 1)
+                        .map(|p| p.distance(pointer)),
+                );
+            for distance in distances {
+                if distance < nearest_distance {
+                    nearest_distance = distance;
+                    nearest = Some(file_entry.id);
+                }
+            }
+        }
+    }
+    nearest
+}
+
+/// the data point of the hovered file closest to the pointer, or the pointer
+/// coordinates if no file is hovered
+fn values_at_cursor(
+    plot_ui: &egui_plot::PlotUi,
+    folders: &[Folder],
+    hovered: Option<usize>,
+    flip: AxisFlip,
+) -> Option<[f64; 2]> {
+    let pointer = plot_ui.pointer_coordinate()?;
+    let Some(file_entry) = folders
+        .iter()
+        .flat_map(|folder| &folder.files)
+        .find(|file_entry| Some(file_entry.id) == hovered)
+    else {
+        return Some(flip.point([pointer.x, pointer.y]));
+    };
+    let pointer = plot_ui.screen_from_plot(pointer);
+    file_entry
+        .transformed_data()
+        .into_iter()
+        .filter(|[x, y]| x.is_finite() && y.is_finite())
+        .min_by(|a, b| {
+            let distance = |[x, y]: &[f64; 2]| {
+                plot_ui
+                    .screen_from_plot(flip.plot_point(egui_plot::PlotPoint::new(*x, *y)))
+                    .distance_sq(pointer)
+            };
+            distance(a).total_cmp(&distance(b))
+        })
+}
+
+/// name, index and, if the rows were kept, file row of the point of the
+/// hovered file closest to the pointer
+fn point_at_cursor(
+    plot_ui: &egui_plot::PlotUi,
+    folders: &mut [Folder],
+    hovered: Option<usize>,
+    flip: AxisFlip,
+) -> Option<String> {
+    let pointer = plot_ui.screen_from_plot(plot_ui.pointer_coordinate()?);
+    let file_entry = folders
+        .iter_mut()
+        .flat_map(|folder| &mut folder.files)
+        .find(|file_entry| Some(file_entry.id) == hovered)?;
+    let (data, index) = file_entry.indexed_data();
+    let (_, i) = data
+        .iter()
+        .zip(index.iter().copied())
+        .filter(|([x, y], _)| x.is_finite() && y.is_finite())
+        .min_by(|(a, _), (b, _)| {
+            let distance = |[x, y]: &[f64; 2]| {
+                plot_ui
+                    .screen_from_plot(flip.plot_point(egui_plot::PlotPoint::new(*x, *y)))
+                    .distance_sq(pointer)
+            };
+            distance(a).total_cmp(&distance(b))
+        })?;
+    let mut info = format!("{}\npoint {}", file_entry.display_name(), i);
+    if let Some(row) = file_entry.row_of(i) {
+        info.push_str(&format!(", row {}", row));
+    }
+    Some(info)
+}
+
+fn point_segment_distance(p: egui::Pos2, a: egui::Pos2, b: egui::Pos2) -> f32 {
+    let ab = b - a;
+    let t = if ab.length_sq() == 0.0 {
+        0.0
+    } else {
+        ((p - a).dot(ab) / ab.length_sq()).clamp(0.0, 1.0)
+    };
+    p.distance(a + t * ab)
+}
+
+/// x- and y-range of all data drawn in `tab`
+fn data_extent(folders: &[Folder], tab: usize) -> Option<([f64; 2], [f64; 2])> {
+    folders
+        .iter()
+        .flat_map(|folder| &folder.files)
+        .filter(|file_entry| file_entry.is_drawn_in(tab))
+        .flat_map(|file_entry| file_entry.transformed_data())
+        .filter(|[x, y]| x.is_finite() && y.is_finite())
+        .fold(None, |extent, [x, y]| match extent {
+            None => Some(([x, x], [y, y])),
+            Some(([x0, x1], [y0, y1])) => Some(([x.min(x0), x.max(x1)], [y.min(y0), y.max(y1)])),
+        })
+}
+
+/// space around the range `lo..hi` when the view is fit to it, ranges
+/// without a width get a margin relative to their value
+fn view_margin(lo: f64, hi: f64) -> f64 {
+    match (hi - lo) * 0.05 {
+        m if m > 0.0 => m,
+        _ => lo.abs().max(1.0) * 0.05,
+    }
+}
+
+/// y-range of all transformed points drawn in `tab` with x in `[x0, x1]`
+fn visible_y_range(folders: &[Folder], tab: usize, x0: f64, x1: f64) -> Option<(f64, f64)> {
+    folders
+        .iter()
+        .flat_map(|folder| &folder.files)
+        .filter(|file_entry| file_entry.is_drawn_in(tab))
+        .flat_map(|file_entry| file_entry.transformed_data())
+        .filter(|[x, y]| (x0..=x1).contains(x) && y.is_finite())
+        .fold(None, |range, [_, y]| match range {
+            None => Some((y, y)),
+            Some((lo, hi)) => Some((y.min(lo), y.max(hi))),
+        })
+}
+
+// below this distance, colors are considered hard to distinguish
+const SIMILAR_COLOR_DISTANCE: f32 = 60.0;
+
+/// perceptual distance of two colors ("redmean" approximation), ranging
+/// from 0 to about 765
+fn color_distance(a: Color32, b: Color32) -> f32 {
+    let [r1, g1, b1, _] = a.to_srgba_unmultiplied().map(|c| c as f32);
+    let [r2, g2, b2, _] = b.to_srgba_unmultiplied().map(|c| c as f32);
+    let rmean = (r1 + r2) / 2.0;
+    let (dr, dg, db) = (r1 - r2, g1 - g2, b1 - b2);
+    ((2.0 + rmean / 256.0) * dr * dr + 4.0 * dg * dg + (2.0 + (255.0 - rmean) / 256.0) * db * db)
+        .sqrt()
+}
+
+/// which slots of `auto_color` are assigned to newly plotted files
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ColorCycle {
+    /// color index of the first plotted file
+    pub start: i32,
+    /// number of colors before the cycle repeats, 0 for no repetition
+    pub length: i32,
+    /// imported colors that replace the generated ones if not empty
+    #[serde(default)]
+    pub palette: Vec<Color32>,
+}
+
+impl Default for ColorCycle {
+    fn default() -> Self {
+        Self {
+            start: 1,
+            length: 0,
+            palette: Vec::new(),
+        }
+    }
+}
+
+impl ColorCycle {
+    /// color index of the `n`-th (0-based) automatically colored file
+    fn color_idx(&self, n: i32) -> i32 {
+        let n = if self.length > 0 { n % self.length } else { n };
+        self.start + n
+    }
+    /// color of the `n`-th (0-based) automatically colored file, with the
+    /// default start the palette begins with its first color
+    pub fn color(&self, n: i32) -> Color32 {
+        let idx = self.color_idx(n);
+        if self.palette.is_empty() {
+            auto_color(idx)
+        } else {
+            self.palette[(idx - 1).rem_euclid(self.palette.len() as i32) as usize]
+        }
+    }
+    fn import_palette(&mut self) -> Result<String, String> {
+        let Some(path) = rfd::FileDialog::new()
+            .add_filter("palette", &["txt", "json"])
+            .pick_file()
+        else {
+            return Err("WARNING: no palette file selected.".to_string());
+        };
+        let text = std::fs::read_to_string(&path)
+            .map_err(|err| format!("ERROR: could not read palette {:?}: {}", path, err))?;
+        self.palette = parse_palette(&text)
+            .map_err(|err| format!("ERROR: invalid palette {:?}: {}", path, err))?;
+        Ok(format!(
+            "INFO: imported {} colors from {:?}.",
+            self.palette.len(),
+            path
+        ))
+    }
+    pub fn ui(&mut self, ui: &mut egui::Ui, errors: &mut Vec<String>) {
+        egui::Grid::new("color_cycle")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("First color");
+                ui.add(egui::DragValue::new(&mut self.start));
+                ui.end_row();
+                ui.label("Colors per cycle");
+                ui.add(egui::DragValue::new(&mut self.length).range(0..=100))
+                    .on_hover_text("0 for no repetition");
+                ui.end_row();
+            });
+        ui.horizontal(|ui| {
+            for n in 0..10 {
+                let (rect, _) =
+                    ui.allocate_exact_size(egui::vec2(12.0, 12.0), egui::Sense::hover());
+                ui.painter().rect_filled(rect, 2.0, self.color(n));
+            }
+        });
+        ui.horizontal(|ui| {
+            if ui
+                .button("Import Palette")
+                .on_hover_text("Text file of hex colors like #1f77b4, or a JSON list of them")
+                .clicked()
+            {
+                match self.import_palette() {
+                    Ok(msg) | Err(msg) => errors.push(msg),
+                }
+            }
+            if !self.palette.is_empty() && ui.button("Clear Palette").clicked() {
+                self.palette.clear();
+            }
+        });
+        if ui.button("Reset Colors").clicked() {
+            *self = ColorCycle::default();
+        }
+    }
+}
+
+/// color from the next slot of the running color index
+pub fn next_auto_color(ctx: &egui::Context, cycle: &ColorCycle) -> Color32 {
+    let n = ctx.data_mut(|map| {
+        let n = map.get_temp_mut_or_insert_with(Id::new("color_idx"), || 0);
+        *n += 1;
+        *n - 1
+    });
+    cycle.color(n)
+}
+
+/// colors of a JSON list of hex strings, or of hex strings separated by
+/// whitespace, commas or semicolons
+fn parse_palette(text: &str) -> Result<Vec<Color32>, String> {
+    let hex_colors: Vec<String> = if text.trim_start().starts_with('[') {
+        serde_json::from_str(text).map_err(|err| err.to_string())?
+    } else {
+        text.split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+            .filter(|hex| !hex.is_empty())
+            .map(str::to_string)
+            .collect()
+    };
+    let colors = hex_colors
+        .iter()
+        .map(|hex| {
+            let hex = hex.trim();
+            // the # is optional in the file
+            let prefixed = format!("#{}", hex.trim_start_matches('#'));
+            Color32::from_hex(&prefixed).map_err(|_| format!("{:?} is no hex color", hex))
+        })
+        .collect::<Result<Vec<Color32>, String>>()?;
+    if colors.is_empty() {
+        return Err("no colors found".to_string());
+    }
+    Ok(colors)
 }
 
 pub fn auto_color(color_idx: i32) -> Color32 {
@@ -147,3 +1771,94 @@ pub fn auto_color(color_idx: i32) -> Color32 {
     // also updates the color index
     Hsva::new(h, 0.85, 0.5, 1.0).into()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::ManipulationDefaults;
+    use crate::file_entry::get_file_entries;
+
+    fn file_entry(id: usize, tab: usize, active: bool) -> FileEntry {
+        // tests run in parallel, each on its own thread
+        let thread = format!("{:?}", std::thread::current().id());
+        let thread: String = thread.chars().filter(char::is_ascii_digit).collect();
+        let dir = std::env::temp_dir().join(format!("plotme_plot_{thread}"));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(format!("{id}.csv")), "").unwrap();
+        // the entry gets the next id of the counter
+        let mut next_id = id;
+        let mut file_entry = get_file_entries(
+            &dir,
+            &mut next_id,
+            &ManipulationDefaults::default(),
+            &mut vec![],
+        )
+        .pop()
+        .unwrap();
+        std::fs::remove_dir_all(dir).unwrap();
+        file_entry.tab = Some(tab);
+        if active {
+            file_entry.set_active();
+        }
+        file_entry
+    }
+
+    #[test]
+    fn shared_cursor_follows_the_hovered_panel() {
+        let mut cursor = SharedCursor::default();
+        assert_eq!(cursor.x(), None);
+        cursor.hover(0, 1.5);
+        assert_eq!(cursor.x(), Some(1.5));
+        // the pointer moved from panel 0 to panel 1 before panel 0 saw it leave
+        cursor.hover(1, 2.5);
+        cursor.leave(0);
+        assert_eq!(cursor.x(), Some(2.5));
+        cursor.leave(1);
+        assert_eq!(cursor.x(), None);
+    }
+
+    #[test]
+    fn drag_targets_are_the_active_files_of_the_tab() {
+        let files = [
+            file_entry(0, 0, true),
+            file_entry(1, 0, false),
+            file_entry(2, 1, true),
+            file_entry(3, 0, true),
+        ];
+        assert_eq!(drag_targets(&files, 0, false, None), vec![0, 3]);
+        assert_eq!(drag_targets(&files, 1, false, None), vec![2]);
+    }
+
+    #[test]
+    fn offset_y_drag_moves_only_active_files() {
+        let mut app = App::default();
+        app.plot_tabs.active_mut().dims = PlotDimensions {
+            x0: 0.0,
+            x1: 10.0,
+            y0: 0.0,
+            y1: 10.0,
+        };
+        let files = vec![
+            file_entry(0, 0, true),
+            file_entry(1, 0, false),
+            file_entry(2, 1, true),
+        ];
+        app.folders = vec![Folder::new("data".into(), files)];
+        app.apply_drag(true, false, false, egui::vec2(0.0, 5.0), 1.0);
+        let offsets: Vec<&str> = app.folders[0]
+            .files
+            .iter()
+            .map(|file_entry| file_entry.offset.input.as_str())
+            .collect();
+        assert_ne!(offsets[0], "0.0");
+        assert_eq!(offsets[1], "0.0");
+        assert_eq!(offsets[2], "0.0");
+    }
+
+    #[test]
+    fn drag_targets_with_primary_only() {
+        let files = [file_entry(0, 0, true), file_entry(1, 0, true)];
+        assert_eq!(drag_targets(&files, 0, true, Some(1)), vec![1]);
+        assert!(drag_targets(&files, 0, true, None).is_empty());
+    }
+}
@@ -0,0 +1,123 @@
+// resampling of data onto a common x-grid, the backbone of the features that
+// combine or compare several files
+
+/// the finite points of `data`, sorted by x
+pub fn sorted_finite(data: &[[f64; 2]]) -> Vec<[f64; 2]> {
+    let mut data: Vec<[f64; 2]> = data
+        .iter()
+        .copied()
+        .filter(|[x, y]| x.is_finite() && y.is_finite())
+        .collect();
+    data.sort_by(|a, b| a[0].total_cmp(&b[0]));
+    data
+}
+
+/// `num_points` evenly spaced values from `start` to `end`, a single point
+/// lies at `start`
+pub fn even_grid(start: f64, end: f64, num_points: usize) -> Vec<f64> {
+    match num_points {
+        0 => vec![],
+        1 => vec![start],
+        n => (0..n)
+            .map(|i| start + (end - start) * i as f64 / (n - 1) as f64)
+            .collect(),
+    }
+}
+
+/// y of `data` at every x of `grid`, linearly interpolated between the finite
+/// points of `data`, which does not have to be sorted by x; there is no
+/// extrapolation, grid points outside of the x-range of `data` are NaN, as
+/// are all points if `data` is empty; a single point only yields a value at
+/// its own x
+pub fn resample_linear(data: &[[f64; 2]], grid: &[f64]) -> Vec<f64> {
+    let data = sorted_finite(data);
+    grid.iter()
+        .map(|&x| interpolate(&data, x).unwrap_or(f64::NAN))
+        .collect()
+}
+
+/// y at `x` on the line through the points of `data`, which is sorted by x,
+/// `None` outside of its x-range
+fn interpolate(data: &[[f64; 2]], x: f64) -> Option<f64> {
+    let (first, last) = (data.first()?, data.last()?);
+    if !(first[0]..=last[0]).contains(&x) {
+        return None;
+    }
+    let idx = data.partition_point(|[xi, _]| *xi < x);
+    if idx == 0 {
+        return Some(first[1]);
+    }
+    let ([xa, ya], [xb, yb]) = (data[idx - 1], data[idx]);
+    if xb == xa {
+        return Some(yb);
+    }
+    Some(ya + (yb - ya) * (x - xa) / (xb - xa))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn monotonic_input() {
+        let data = [[0.0, 0.0], [1.0, 10.0], [3.0, 30.0]];
+        let resampled = resample_linear(&data, &[0.0, 0.5, 1.0, 2.0, 3.0]);
+        assert_eq!(resampled, vec![0.0, 5.0, 10.0, 20.0, 30.0]);
+    }
+
+    #[test]
+    fn non_monotonic_input_is_sorted() {
+        let data = [[3.0, 30.0], [0.0, 0.0], [1.0, 10.0]];
+        let resampled = resample_linear(&data, &[0.5, 2.0]);
+        assert_eq!(resampled, vec![5.0, 20.0]);
+    }
+
+    #[test]
+    fn non_finite_points_are_skipped() {
+        let data = [
+            [0.0, 0.0],
+            [1.0, f64::NAN],
+            [2.0, 20.0],
+            [f64::INFINITY, 5.0],
+        ];
+        let resampled = resample_linear(&data, &[1.0]);
+        assert_eq!(resampled, vec![10.0]);
+    }
+
+    #[test]
+    fn no_extrapolation_outside_of_the_range() {
+        let data = [[0.0, 0.0], [1.0, 10.0]];
+        let resampled = resample_linear(&data, &[-0.1, 1.1]);
+        assert!(resampled.iter().all(|y| y.is_nan()));
+    }
+
+    #[test]
+    fn empty_input() {
+        let resampled = resample_linear(&[], &[0.0, 1.0]);
+        assert_eq!(resampled.len(), 2);
+        assert!(resampled.iter().all(|y| y.is_nan()));
+        assert!(resample_linear(&[[0.0, 1.0]], &[]).is_empty());
+    }
+
+    #[test]
+    fn single_point() {
+        let resampled = resample_linear(&[[1.0, 7.0]], &[0.0, 1.0, 2.0]);
+        assert!(resampled[0].is_nan());
+        assert_eq!(resampled[1], 7.0);
+        assert!(resampled[2].is_nan());
+    }
+
+    #[test]
+    fn duplicate_x_values() {
+        let data = [[0.0, 0.0], [1.0, 10.0], [1.0, 20.0], [2.0, 20.0]];
+        let resampled = resample_linear(&data, &[0.5, 1.5]);
+        assert_eq!(resampled, vec![5.0, 20.0]);
+    }
+
+    #[test]
+    fn even_grid_points() {
+        assert_eq!(even_grid(0.0, 1.0, 3), vec![0.0, 0.5, 1.0]);
+        assert_eq!(even_grid(2.0, 5.0, 1), vec![2.0]);
+        assert!(even_grid(0.0, 1.0, 0).is_empty());
+    }
+}